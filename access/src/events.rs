@@ -0,0 +1,80 @@
+use near_sdk::serde_json::{json, Value};
+use near_sdk::{env, AccountId};
+
+use crate::{Feature, Role};
+
+/// Logs a NEP-297 event of the Scity access standard.
+fn emit(event: &str, data: Value) {
+    let event = json!({
+        "standard": "scity_access",
+        "version": "1.0.0",
+        "event": event,
+        "data": [data],
+    });
+    env::log(format!("EVENT_JSON:{}", event).as_bytes());
+}
+
+pub fn emit_role_granted(role: Role, account_id: &AccountId, sender_id: &AccountId) {
+    emit(
+        "role_granted",
+        json!({ "role": role, "account_id": account_id, "sender_id": sender_id }),
+    );
+}
+
+pub fn emit_role_revoked(role: Role, account_id: &AccountId, sender_id: &AccountId) {
+    emit(
+        "role_revoked",
+        json!({ "role": role, "account_id": account_id, "sender_id": sender_id }),
+    );
+}
+
+pub fn emit_role_renounced(role: Role, account_id: &AccountId) {
+    emit(
+        "role_renounced",
+        json!({ "role": role, "account_id": account_id }),
+    );
+}
+
+pub fn emit_ownership_transfer_proposed(old_owner_id: &AccountId, new_owner_id: &AccountId) {
+    emit(
+        "ownership_transfer_proposed",
+        json!({ "old_owner_id": old_owner_id, "new_owner_id": new_owner_id }),
+    );
+}
+
+pub fn emit_ownership_transferred(old_owner_id: &AccountId, new_owner_id: &AccountId) {
+    emit(
+        "ownership_transferred",
+        json!({ "old_owner_id": old_owner_id, "new_owner_id": new_owner_id }),
+    );
+}
+
+pub fn emit_ownership_transfer_cancelled(owner_id: &AccountId, pending_owner_id: &AccountId) {
+    emit(
+        "ownership_transfer_cancelled",
+        json!({ "owner_id": owner_id, "pending_owner_id": pending_owner_id }),
+    );
+}
+
+/// `feature` is `null` when the whole contract is paused.
+pub fn emit_paused(feature: Option<Feature>, sender_id: &AccountId) {
+    emit(
+        "paused",
+        json!({ "feature": feature, "sender_id": sender_id }),
+    );
+}
+
+pub fn emit_unpaused(feature: Option<Feature>, sender_id: &AccountId) {
+    emit(
+        "unpaused",
+        json!({ "feature": feature, "sender_id": sender_id }),
+    );
+}
+
+/// A setting of the contract, e.g. the account of another contract, was changed.
+pub fn emit_config_updated(field: &str, old: &str, new: &str, sender_id: &AccountId) {
+    emit(
+        "config_updated",
+        json!({ "field": field, "old": old, "new": new, "sender_id": sender_id }),
+    );
+}
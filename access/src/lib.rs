@@ -0,0 +1,225 @@
+//! Role-based access control shared by the Scity contracts.
+//!
+//! The contract owner implicitly holds every [`Role`]. [`AccessControl`] stores the accounts
+//! granted each role, and contracts expose the grant, revoke and renounce methods with the
+//! [`impl_access_control!`] macro. Ownership itself moves with the two-step flow of
+//! [`impl_ownership!`], and [`impl_pausable!`] lets pausers stop the whole contract or single
+//! [`Feature`]s. Every change is logged as a NEP-297 event.
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, UnorderedSet};
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, AccountId, IntoStorageKey};
+
+pub use crate::events::*;
+pub use crate::pausable::*;
+
+mod events;
+mod macros;
+mod pausable;
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Grants and revokes every role except admin.
+    Admin,
+    Minter,
+    Pauser,
+    AreaManager,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Minter => "minter",
+            Role::Pauser => "pauser",
+            Role::AreaManager => "area_manager",
+        }
+    }
+}
+
+/// Helper structure to for keys of the persistent collections, nested under the prefix the
+/// contract passes to [`AccessControl::new`].
+#[derive(BorshSerialize)]
+enum StorageKey {
+    Members,
+    MembersInner { role: Role },
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AccessControl {
+    members: LookupMap<Role, UnorderedSet<AccountId>>,
+
+    prefix: Vec<u8>,
+}
+
+impl AccessControl {
+    pub fn new<P>(prefix: P) -> Self
+    where
+        P: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        Self {
+            members: LookupMap::new(storage_key(&prefix, StorageKey::Members)),
+            prefix,
+        }
+    }
+
+    pub fn has_role(&self, role: Role, account_id: &AccountId) -> bool {
+        self.members
+            .get(&role)
+            .map(|members| members.contains(account_id))
+            .unwrap_or(false)
+    }
+
+    /// Returns whether the account did not hold the role yet.
+    pub fn grant(&mut self, role: Role, account_id: &AccountId) -> bool {
+        let mut members = self.members.get(&role).unwrap_or_else(|| {
+            UnorderedSet::new(storage_key(&self.prefix, StorageKey::MembersInner { role }))
+        });
+        let added = members.insert(account_id);
+        self.members.insert(&role, &members);
+        added
+    }
+
+    /// Returns whether the account held the role.
+    pub fn revoke(&mut self, role: Role, account_id: &AccountId) -> bool {
+        match self.members.get(&role) {
+            Some(mut members) => {
+                let removed = members.remove(account_id);
+                self.members.insert(&role, &members);
+                removed
+            }
+            None => false,
+        }
+    }
+
+    pub fn members(&self, role: Role) -> Vec<AccountId> {
+        self.members
+            .get(&role)
+            .map(|members| members.to_vec())
+            .unwrap_or_default()
+    }
+}
+
+pub trait AccessControlCore {
+    fn grant_role(&mut self, role: Role, account_id: ValidAccountId);
+
+    fn revoke_role(&mut self, role: Role, account_id: ValidAccountId);
+
+    /// Drops a role the caller holds.
+    fn renounce_role(&mut self, role: Role);
+
+    /// Whether the account holds the role, always true for the owner.
+    fn has_role(&self, role: Role, account_id: ValidAccountId) -> bool;
+
+    /// Accounts granted the role, not including the owner.
+    fn get_role_members(&self, role: Role) -> Vec<AccountId>;
+}
+
+/// Two-step ownership transfer: the owner proposes a new owner, who must accept before any
+/// privileges move.
+pub trait OwnershipCore {
+    fn propose_owner(&mut self, new_owner_id: ValidAccountId);
+
+    fn accept_ownership(&mut self);
+
+    fn cancel_ownership_transfer(&mut self);
+
+    fn get_owner(&self) -> AccountId;
+
+    fn get_pending_owner(&self) -> Option<AccountId>;
+}
+
+/// Panics unless the caller is `owner_id` or holds `role`.
+pub fn assert_role(access: &AccessControl, owner_id: &AccountId, role: Role) {
+    let caller = env::predecessor_account_id();
+    assert!(
+        &caller == owner_id || access.has_role(role, &caller),
+        "Caller does not have the {} role",
+        role.as_str()
+    );
+}
+
+fn storage_key(prefix: &[u8], key: StorageKey) -> Vec<u8> {
+    [prefix, &key.try_to_vec().unwrap()].concat()
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use super::*;
+
+    #[test]
+    fn test_grant_and_revoke() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut access = AccessControl::new(b"r".to_vec());
+        let minter: AccountId = accounts(1).into();
+
+        assert!(access.grant(Role::Minter, &minter));
+        assert!(!access.grant(Role::Minter, &minter));
+        assert!(access.has_role(Role::Minter, &minter));
+        assert!(!access.has_role(Role::Pauser, &minter));
+        assert_eq!(access.members(Role::Minter), vec![minter.clone()]);
+
+        assert!(access.revoke(Role::Minter, &minter));
+        assert!(!access.revoke(Role::Minter, &minter));
+        assert!(access.members(Role::Minter).is_empty());
+    }
+
+    #[test]
+    fn test_assert_role() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut access = AccessControl::new(b"r".to_vec());
+        let owner_id: AccountId = accounts(0).into();
+        assert_role(&access, &owner_id, Role::Pauser);
+
+        access.grant(Role::Pauser, &accounts(1).into());
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        assert_role(&access, &owner_id, Role::Pauser);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the area_manager role")]
+    fn test_assert_role_missing() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut access = AccessControl::new(b"r".to_vec());
+        access.grant(Role::Minter, &accounts(1).into());
+        assert_role(&access, &accounts(0).into(), Role::AreaManager);
+    }
+
+    #[test]
+    fn test_pause_features() {
+        let mut pausable = Pausable::default();
+        assert!(pausable.pause(Some(Feature::Sales)));
+        assert!(!pausable.pause(Some(Feature::Sales)));
+        assert!(pausable.is_paused(Feature::Sales));
+        assert!(!pausable.is_paused(Feature::Transfers));
+
+        assert!(pausable.pause(None));
+        assert!(pausable.is_paused(Feature::Transfers));
+        assert!(pausable.unpause(None));
+        assert!(!pausable.is_paused(Feature::Transfers));
+        assert!(pausable.is_paused(Feature::Sales));
+
+        assert!(pausable.unpause(Some(Feature::Sales)));
+        assert!(!pausable.unpause(Some(Feature::Sales)));
+        assert!(!pausable.is_paused(Feature::Sales));
+    }
+
+    #[test]
+    #[should_panic(expected = "The box_opening feature is paused")]
+    fn test_assert_not_paused() {
+        let mut pausable = Pausable::default();
+        pausable.pause(None);
+        pausable.assert_not_paused(Feature::BoxOpening);
+    }
+}
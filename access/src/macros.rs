@@ -0,0 +1,177 @@
+/// Exposes role management on the contract's [`AccessControl`](crate::AccessControl) field and
+/// adds an `assert_role` helper for privileged methods. The owner grants and revokes admins,
+/// admins manage the other roles.
+///
+/// The contract module must have `AccessControlCore` in scope.
+#[macro_export]
+macro_rules! impl_access_control {
+    ($contract: ident, $access: ident, $owner_id: ident) => {
+        #[near_sdk::near_bindgen]
+        impl $crate::AccessControlCore for $contract {
+            fn grant_role(
+                &mut self,
+                role: $crate::Role,
+                account_id: near_sdk::json_types::ValidAccountId,
+            ) {
+                self.assert_role_manager(role);
+                let account_id: near_sdk::AccountId = account_id.into();
+                if self.$access.grant(role, &account_id) {
+                    $crate::emit_role_granted(
+                        role,
+                        &account_id,
+                        &near_sdk::env::predecessor_account_id(),
+                    );
+                }
+            }
+
+            fn revoke_role(
+                &mut self,
+                role: $crate::Role,
+                account_id: near_sdk::json_types::ValidAccountId,
+            ) {
+                self.assert_role_manager(role);
+                let account_id: near_sdk::AccountId = account_id.into();
+                if self.$access.revoke(role, &account_id) {
+                    $crate::emit_role_revoked(
+                        role,
+                        &account_id,
+                        &near_sdk::env::predecessor_account_id(),
+                    );
+                }
+            }
+
+            fn renounce_role(&mut self, role: $crate::Role) {
+                let account_id = near_sdk::env::predecessor_account_id();
+                assert!(
+                    self.$access.revoke(role, &account_id),
+                    "Caller does not have the {} role",
+                    role.as_str()
+                );
+                $crate::emit_role_renounced(role, &account_id);
+            }
+
+            fn has_role(
+                &self,
+                role: $crate::Role,
+                account_id: near_sdk::json_types::ValidAccountId,
+            ) -> bool {
+                let account_id: near_sdk::AccountId = account_id.into();
+                account_id == self.$owner_id || self.$access.has_role(role, &account_id)
+            }
+
+            fn get_role_members(&self, role: $crate::Role) -> Vec<near_sdk::AccountId> {
+                self.$access.members(role)
+            }
+        }
+
+        impl $contract {
+            /// Panics unless the caller is the owner or holds `role`.
+            pub(crate) fn assert_role(&self, role: $crate::Role) {
+                $crate::assert_role(&self.$access, &self.$owner_id, role)
+            }
+
+            fn assert_role_manager(&self, role: $crate::Role) {
+                if role == $crate::Role::Admin {
+                    assert!(
+                        near_sdk::env::predecessor_account_id() == self.$owner_id,
+                        "Only the owner can manage admins"
+                    );
+                } else {
+                    self.assert_role($crate::Role::Admin);
+                }
+            }
+        }
+    };
+}
+
+/// Exposes the two-step ownership transfer over the contract's owner and pending owner fields.
+///
+/// The contract module must have `OwnershipCore` in scope.
+#[macro_export]
+macro_rules! impl_ownership {
+    ($contract: ident, $owner_id: ident, $pending_owner_id: ident) => {
+        #[near_sdk::near_bindgen]
+        impl $crate::OwnershipCore for $contract {
+            /// Replaces any earlier proposal.
+            fn propose_owner(&mut self, new_owner_id: near_sdk::json_types::ValidAccountId) {
+                assert!(
+                    near_sdk::env::predecessor_account_id() == self.$owner_id,
+                    "Caller is not owner."
+                );
+                let new_owner_id: near_sdk::AccountId = new_owner_id.into();
+                assert!(
+                    new_owner_id != self.$owner_id,
+                    "Account is already the owner"
+                );
+                $crate::emit_ownership_transfer_proposed(&self.$owner_id, &new_owner_id);
+                self.$pending_owner_id = Some(new_owner_id);
+            }
+
+            fn accept_ownership(&mut self) {
+                let caller = near_sdk::env::predecessor_account_id();
+                assert!(
+                    self.$pending_owner_id.as_ref() == Some(&caller),
+                    "Caller is not the pending owner"
+                );
+                $crate::emit_ownership_transferred(&self.$owner_id, &caller);
+                self.$owner_id = caller;
+                self.$pending_owner_id = None;
+            }
+
+            fn cancel_ownership_transfer(&mut self) {
+                assert!(
+                    near_sdk::env::predecessor_account_id() == self.$owner_id,
+                    "Caller is not owner."
+                );
+                let pending_owner_id = self
+                    .$pending_owner_id
+                    .take()
+                    .expect("No pending ownership transfer");
+                $crate::emit_ownership_transfer_cancelled(&self.$owner_id, &pending_owner_id);
+            }
+
+            fn get_owner(&self) -> near_sdk::AccountId {
+                self.$owner_id.clone()
+            }
+
+            fn get_pending_owner(&self) -> Option<near_sdk::AccountId> {
+                self.$pending_owner_id.clone()
+            }
+        }
+    };
+}
+
+/// Exposes pausing of the whole contract or single features over the contract's
+/// [`Pausable`](crate::Pausable) field, restricted to the pauser role. Requires
+/// [`impl_access_control!`] on the same contract.
+///
+/// The contract module must have `PausableCore` in scope.
+#[macro_export]
+macro_rules! impl_pausable {
+    ($contract: ident, $pausable: ident) => {
+        #[near_sdk::near_bindgen]
+        impl $crate::PausableCore for $contract {
+            fn pause(&mut self, feature: Option<$crate::Feature>) {
+                self.assert_role($crate::Role::Pauser);
+                if self.$pausable.pause(feature) {
+                    $crate::emit_paused(feature, &near_sdk::env::predecessor_account_id());
+                }
+            }
+
+            fn unpause(&mut self, feature: Option<$crate::Feature>) {
+                self.assert_role($crate::Role::Pauser);
+                if self.$pausable.unpause(feature) {
+                    $crate::emit_unpaused(feature, &near_sdk::env::predecessor_account_id());
+                }
+            }
+
+            fn is_paused(&self, feature: $crate::Feature) -> bool {
+                self.$pausable.is_paused(feature)
+            }
+
+            fn get_pause_status(&self) -> $crate::Pausable {
+                self.$pausable.clone()
+            }
+        }
+    };
+}
@@ -0,0 +1,96 @@
+use crate::*;
+
+/// Parts of a contract that can be paused on their own.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    Sales,
+    Transfers,
+    Minting,
+    BoxOpening,
+    Staking,
+    /// Company upgrades, whatever they are paid in.
+    Upgrades,
+    /// Placing companies on lands, unplacing and claiming them back.
+    Placement,
+    /// Claiming mining rewards. Rewards keep accruing while paused.
+    Mining,
+    Fusion,
+}
+
+impl Feature {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Feature::Sales => "sales",
+            Feature::Transfers => "transfers",
+            Feature::Minting => "minting",
+            Feature::BoxOpening => "box_opening",
+            Feature::Staking => "staking",
+            Feature::Upgrades => "upgrades",
+            Feature::Placement => "placement",
+            Feature::Mining => "mining",
+            Feature::Fusion => "fusion",
+        }
+    }
+}
+
+/// Pause state of a contract. A global pause stops every feature regardless of the features
+/// paused individually.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Pausable {
+    pub all: bool,
+    pub features: Vec<Feature>,
+}
+
+impl Pausable {
+    pub fn is_paused(&self, feature: Feature) -> bool {
+        self.all || self.features.contains(&feature)
+    }
+
+    pub fn assert_not_paused(&self, feature: Feature) {
+        assert!(
+            !self.is_paused(feature),
+            "The {} feature is paused",
+            feature.as_str()
+        );
+    }
+
+    /// Pauses `feature`, or the whole contract if `None`. Returns whether anything changed.
+    pub fn pause(&mut self, feature: Option<Feature>) -> bool {
+        match feature {
+            Some(feature) if self.features.contains(&feature) => false,
+            Some(feature) => {
+                self.features.push(feature);
+                true
+            }
+            None => !std::mem::replace(&mut self.all, true),
+        }
+    }
+
+    /// Unpauses `feature`, or lifts the global pause if `None`. Features paused individually
+    /// stay paused when the global pause is lifted. Returns whether anything changed.
+    pub fn unpause(&mut self, feature: Option<Feature>) -> bool {
+        match feature {
+            Some(feature) => {
+                let len = self.features.len();
+                self.features.retain(|paused| *paused != feature);
+                self.features.len() != len
+            }
+            None => std::mem::replace(&mut self.all, false),
+        }
+    }
+}
+
+pub trait PausableCore {
+    fn pause(&mut self, feature: Option<Feature>);
+
+    fn unpause(&mut self, feature: Option<Feature>);
+
+    fn is_paused(&self, feature: Feature) -> bool;
+
+    fn get_pause_status(&self) -> Pausable;
+}
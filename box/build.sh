@@ -1,5 +1,5 @@
-#!/bin/bash
-set -e
-cd "`dirname $0`"
-cargo build --all --target wasm32-unknown-unknown --release
-cp target/wasm32-unknown-unknown/release/*.wasm ./res/ft.wasm
\ No newline at end of file
+#!/bin/bash
+set -e
+cd "`dirname $0`"
+cargo build -p fungible-token --target wasm32-unknown-unknown --release
+cp ../target/wasm32-unknown-unknown/release/fungible_token.wasm ./res/ft.wasm
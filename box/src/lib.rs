@@ -1,224 +1,667 @@
-use near_contract_standards::fungible_token::metadata::{
-    FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC,
-};
-use near_contract_standards::fungible_token::FungibleToken;
-use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::LazyOption;
-use near_sdk::json_types::{ValidAccountId, U128};
-use near_sdk::{
-    env, log, near_bindgen, AccountId, Balance, PanicOnDefault, Promise, PromiseOrValue,
-};
-
-near_sdk::setup_alloc!();
-
-#[near_bindgen]
-#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
-pub struct Contract {
-    token: FungibleToken,
-    metadata: LazyOption<FungibleTokenMetadata>,
-    is_open_sell: bool,    
-    price: Balance,
-}
-
-const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
-const TOTAL_SUPPLY: Balance = 1_000_000_000;
-
-
-#[near_bindgen]
-impl Contract {
-    /// Initializes the contract with the given total supply owned by the given `owner_id` with
-    /// default metadata (for example purposes only).
-    #[init]
-    pub fn new_default_meta(owner_id: ValidAccountId) -> Self {
-        Self::new(
-            owner_id,
-            FungibleTokenMetadata {
-                spec: FT_METADATA_SPEC.to_string(),
-                name: "Scity-Box".to_string(),
-                symbol: "SBOX".to_string(),
-                icon: Some(DATA_IMAGE_SVG_NEAR_ICON.to_string()),
-                reference: None,
-                reference_hash: None,
-                decimals: 0,
-            },
-        )
-    }
-
-    /// Initializes the contract with the given total supply owned by the given `owner_id` with
-    /// the given fungible token metadata.
-    #[init]
-    pub fn new(
-        owner_id: ValidAccountId,
-        metadata: FungibleTokenMetadata,
-    ) -> Self {
-        assert!(!env::state_exists(), "Already initialized");
-        metadata.assert_valid();
-        let mut this = Self {
-            token: FungibleToken::new(b"a".to_vec()),
-            metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
-            is_open_sell: true,
-            price: 0,
-        };
-        this.token.internal_register_account(owner_id.as_ref());
-        this.token
-            .internal_deposit(owner_id.as_ref(), TOTAL_SUPPLY.into());
-        this
-    }
-
-    #[payable]
-    pub fn buy_box(&mut self, receiver_id: AccountId, amount: U128) {
-        let initial_storage_usage = env::storage_usage();
-        let mut amount_for_account = self.token.accounts.get(&receiver_id).unwrap_or(0);
-        amount_for_account += amount.0;
-        self.token
-            .accounts
-            .insert(&receiver_id, &amount_for_account);
-        self.token.total_supply = self
-            .token
-            .total_supply
-            .checked_add(amount.0)
-            .unwrap_or_else(|| env::panic(b"Total supply overflow"));
-
-        //refund any excess storage
-        let storage_used = env::storage_usage() - initial_storage_usage;
-        let required_cost = env::storage_byte_cost() * Balance::from(storage_used) + self.price;
-        let attached_deposit = env::attached_deposit();
-
-        assert!(
-            required_cost <= attached_deposit,
-            "Please deposit price equal land price + mint fee: {}, excess mint fee will be refund !",
-            required_cost
-        );
-
-        let refund = attached_deposit - required_cost;
-        if refund > 1 {
-            Promise::new(env::predecessor_account_id()).transfer(refund);
-        }
-    }
-
-    // View methods
-    pub fn get_total_supply(&self) -> Balance {
-        self.token.total_supply
-    }
-
-    pub fn paused(&self) -> bool {
-        self.is_open_sell
-    }
-
-    // Call methods
-    pub fn pause(&mut self) -> bool {
-        self.is_open_sell = false;
-        self.is_open_sell
-    }
-
-    pub fn unpause(&mut self) -> bool {
-        self.is_open_sell = true;
-        self.is_open_sell
-    }
-
-    fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
-        log!("Closed @{} with {}", account_id, balance);
-    }
-
-    fn on_tokens_burned(&mut self, account_id: AccountId, amount: Balance) {
-        log!("Account @{} burned {}", account_id, amount);
-    }
-
-    #[payable]
-    pub fn transfer_box_to_owner(
-        &mut self,
-        receiver_id: ValidAccountId,
-        amount: U128,
-        memo: Option<String>,
-    ) {
-        assert_eq!(
-            env::attached_deposit(),
-            1,
-            "Requires attached deposit of exactly 1 yoctoNEAR",
-        );
-        let sender_id = env::signer_account_id();
-        let amount: Balance = amount.into();
-        self.token
-            .internal_transfer(&sender_id, receiver_id.as_ref(), amount, memo);
-    }
-}
-
-near_contract_standards::impl_fungible_token_core!(Contract, token, on_tokens_burned);
-near_contract_standards::impl_fungible_token_storage!(Contract, token, on_account_closed);
-
-#[near_bindgen]
-impl FungibleTokenMetadataProvider for Contract {
-    fn ft_metadata(&self) -> FungibleTokenMetadata {
-        self.metadata.get().unwrap()
-    }
-}
-
-#[cfg(all(test, not(target_arch = "wasm32")))]
-mod tests {
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
-    use near_sdk::MockedBlockchain;
-    use near_sdk::{testing_env, Balance};
-
-    use super::*;
-
-    fn get_context(predecessor_account_id: ValidAccountId) -> VMContextBuilder {
-        let mut builder = VMContextBuilder::new();
-        builder
-            .current_account_id(accounts(0))
-            .signer_account_id(predecessor_account_id.clone())
-            .predecessor_account_id(predecessor_account_id);
-        builder
-    }
-
-    #[test]
-    fn test_new() {
-        let mut context = get_context(accounts(1));
-        testing_env!(context.build());
-        let contract = Contract::new_default_meta(accounts(1).into());
-        testing_env!(context.is_view(true).build());
-        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
-        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY);
-    }
-
-    #[test]
-    #[should_panic(expected = "The contract is not initialized")]
-    fn test_default() {
-        let context = get_context(accounts(1));
-        testing_env!(context.build());
-        let _contract = Contract::default();
-    }
-
-    #[test]
-    fn test_transfer() {
-        let mut context = get_context(accounts(2));
-        testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into());
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(contract.storage_balance_bounds().min.into())
-            .predecessor_account_id(accounts(1))
-            .build());
-        // Paying for account registration, aka storage deposit
-        contract.storage_deposit(None, None);
-
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(1)
-            .predecessor_account_id(accounts(2))
-            .build());
-        let transfer_amount = TOTAL_SUPPLY / 3;
-        contract.ft_transfer(accounts(1), transfer_amount.into(), None);
-
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .account_balance(env::account_balance())
-            .is_view(true)
-            .attached_deposit(0)
-            .build());
-        assert_eq!(
-            contract.ft_balance_of(accounts(2)).0,
-            (TOTAL_SUPPLY - transfer_amount)
-        );
-        assert_eq!(contract.ft_balance_of(accounts(1)).0, transfer_amount);
-    }
-}
\ No newline at end of file
+use near_contract_standards::fungible_token::metadata::{
+    FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC,
+};
+use near_contract_standards::fungible_token::FungibleToken;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LazyOption;
+use near_sdk::json_types::{ValidAccountId, U128};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, log, near_bindgen, AccountId, Balance, PanicOnDefault, Promise, Timestamp};
+
+pub use crate::sale::*;
+pub use scity_access::*;
+pub use scity_common::*;
+pub use scity_ft_core::*;
+
+mod sale;
+
+near_sdk::setup_alloc!();
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    token: FungibleToken,
+    metadata: LazyOption<FungibleTokenMetadata>,
+    /// Tier of the boxes, e.g. common, rare or legendary. Each tier is its own contract with
+    /// its own price, and opens into the loot table the company contract keeps for it.
+    box_type: String,
+    /// Unit price of a box.
+    price: Balance,
+    bundle_tiers: Vec<BundleTier>,
+    open_time: Option<Timestamp>,
+    close_time: Option<Timestamp>,
+    /// Boxes for sale, held by the contract account. Tracked apart from the account's balance
+    /// so boxes sent to the contract are never sold.
+    sale_inventory: Balance,
+    /// Boxes sold from the sale inventory.
+    boxes_sold: Balance,
+    /// Boxes held by the contract account that are not for sale. The inventory is moved to and
+    /// from it, and only the owner withdraws from it.
+    reserve: Balance,
+    owner_id: AccountId,
+    pending_owner_id: Option<AccountId>,
+    access: AccessControl,
+    pausable: Pausable,
+    /// Box sale revenue.
+    proceeds: ProceedsLedger,
+    treasury: Option<TreasuryConfig>,
+}
+
+const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
+const TOTAL_SUPPLY: Balance = 1_000_000_000;
+
+#[near_bindgen]
+impl Contract {
+    /// Initializes the contract with the given total supply owned by the given `owner_id` with
+    /// default metadata (for example purposes only).
+    #[init]
+    pub fn new_default_meta(owner_id: ValidAccountId, box_type: String) -> Self {
+        Self::new(
+            owner_id,
+            FungibleTokenMetadata {
+                spec: FT_METADATA_SPEC.to_string(),
+                name: "Scity-Box".to_string(),
+                symbol: "SBOX".to_string(),
+                icon: Some(DATA_IMAGE_SVG_NEAR_ICON.to_string()),
+                reference: None,
+                reference_hash: None,
+                decimals: 0,
+            },
+            box_type,
+        )
+    }
+
+    /// Initializes the contract with the given total supply owned by the given `owner_id` with
+    /// the given fungible token metadata.
+    #[init]
+    pub fn new(
+        owner_id: ValidAccountId,
+        metadata: FungibleTokenMetadata,
+        box_type: String,
+    ) -> Self {
+        assert!(!env::state_exists(), "Already initialized");
+        metadata.assert_valid();
+        let mut this = Self {
+            token: FungibleToken::new(b"a".to_vec()),
+            metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
+            box_type,
+            price: 0,
+            bundle_tiers: Vec::new(),
+            open_time: None,
+            close_time: None,
+            sale_inventory: 0,
+            boxes_sold: 0,
+            reserve: TOTAL_SUPPLY,
+            owner_id: owner_id.clone().into(),
+            pending_owner_id: None,
+            access: AccessControl::new(b"r".to_vec()),
+            pausable: Pausable::default(),
+            proceeds: ProceedsLedger::default(),
+            treasury: None,
+        };
+        // The whole supply starts in the reserve, held by the contract account itself.
+        let reserve_id = env::current_account_id();
+        this.token.internal_register_account(&reserve_id);
+        this.token.internal_deposit(&reserve_id, TOTAL_SUPPLY);
+        emit_ft_mint(&reserve_id, TOTAL_SUPPLY, Some("Initial supply"));
+        if reserve_id != this.owner_id {
+            this.token.internal_register_account(owner_id.as_ref());
+        }
+        this
+    }
+
+    // View methods
+    pub fn get_total_supply(&self) -> Balance {
+        self.token.total_supply
+    }
+
+    pub fn get_box_type(&self) -> String {
+        self.box_type.clone()
+    }
+
+    fn assert_owner(&self) {
+        assert!(
+            env::predecessor_account_id() == self.owner_id,
+            "Caller is not owner."
+        );
+    }
+
+    fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
+        log!("Closed @{} with {}", account_id, balance);
+    }
+
+    fn on_tokens_burned(&mut self, account_id: AccountId, amount: Balance) {
+        log!("Account @{} burned {}", account_id, amount);
+    }
+
+    /// Burns boxes held by the caller, e.g. the company contract after opening them.
+    #[payable]
+    pub fn ft_burn(&mut self, amount: U128, memo: Option<String>) {
+        assert_eq!(
+            env::attached_deposit(),
+            1,
+            "Requires attached deposit of exactly 1 yoctoNEAR",
+        );
+        let account_id = env::predecessor_account_id();
+        let amount: Balance = amount.into();
+        assert!(amount > 0, "The amount should be a positive number");
+        self.token.internal_withdraw(&account_id, amount);
+        emit_ft_burn(&account_id, amount, memo.as_deref());
+        self.on_tokens_burned(account_id, amount);
+    }
+}
+
+near_contract_standards::impl_fungible_token_storage!(Contract, token, on_account_closed);
+scity_ft_core::impl_fungible_token_core!(Contract, token, pausable, on_tokens_burned);
+scity_access::impl_access_control!(Contract, access, owner_id);
+scity_access::impl_ownership!(Contract, owner_id, pending_owner_id);
+scity_access::impl_pausable!(Contract, pausable);
+scity_common::impl_treasury!(Contract, proceeds, treasury);
+
+#[near_bindgen]
+impl FungibleTokenMetadataProvider for Contract {
+    fn ft_metadata(&self) -> FungibleTokenMetadata {
+        self.metadata.get().unwrap()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::core::FungibleTokenCore;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk::{MockedBlockchain, PromiseOrValue};
+
+    use super::*;
+
+    const OPEN_TIME: Timestamp = 1_645_030_800_000_000_000;
+    const CLOSE_TIME: Timestamp = 1_650_128_399_000_000_000;
+
+    fn get_context(predecessor_account_id: ValidAccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(5))
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id);
+        builder
+    }
+
+    #[test]
+    fn test_new() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(1), "common".to_string());
+        testing_env!(context.is_view(true).build());
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_balance_of(accounts(5)).0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+        assert_eq!(contract.get_reserve().0, TOTAL_SUPPLY);
+        assert_eq!(contract.get_box_type(), "common");
+    }
+
+    #[test]
+    #[should_panic(expected = "The contract is not initialized")]
+    fn test_default() {
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let _contract = Contract::default();
+    }
+
+    #[test]
+    fn test_transfer() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), "common".to_string());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        // Paying for account registration, aka storage deposit
+        contract.storage_deposit(None, None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.withdraw_reserve(accounts(2), TOTAL_SUPPLY.into());
+        let transfer_amount = TOTAL_SUPPLY / 3;
+        contract.ft_transfer(accounts(1), transfer_amount.into(), None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .account_balance(env::account_balance())
+            .is_view(true)
+            .attached_deposit(0)
+            .build());
+        assert_eq!(
+            contract.ft_balance_of(accounts(2)).0,
+            (TOTAL_SUPPLY - transfer_amount)
+        );
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, transfer_amount);
+    }
+
+    #[test]
+    fn test_box_proceeds() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), "common".to_string());
+        contract.set_box_price(1_000.into());
+        contract.set_sale_inventory(10.into());
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(10u128.pow(24))
+            .build());
+        contract.buy_box(accounts(1), 1.into());
+        contract.buy_box(accounts(1), 1.into());
+        assert_eq!(contract.get_proceeds().collected.0, 2_000);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0)
+            .build());
+        contract.set_treasury(TreasuryConfig {
+            team_account_id: accounts(3).into(),
+            dao_account_id: accounts(4).into(),
+            team_share: 6_000,
+            dao_share: 4_000,
+            burn_share: 0,
+        });
+        assert!(matches!(
+            contract.withdraw_proceeds(),
+            PromiseOrValue::Promise(_)
+        ));
+        let proceeds = contract.get_proceeds();
+        assert_eq!(proceeds.withdrawn.0, 2_000);
+        assert_eq!(proceeds.available.0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller is not owner.")]
+    fn test_withdraw_proceeds_not_owner() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), "common".to_string());
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.withdraw_proceeds();
+    }
+
+    #[test]
+    fn test_pauser_role() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), "common".to_string());
+        contract.grant_role(Role::Pauser, accounts(1));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.pause(None);
+        contract.unpause(None);
+        assert_eq!(
+            contract.get_role_members(Role::Pauser),
+            vec![accounts(1).to_string()]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the pauser role")]
+    fn test_pause_without_role() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), "common".to_string());
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.pause(None);
+    }
+
+    #[test]
+    fn test_ownership_transfer() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), "common".to_string());
+        contract.propose_owner(accounts(1));
+        contract.pause(None);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.accept_ownership();
+        assert_eq!(contract.get_owner(), accounts(1).to_string());
+        contract.unpause(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the pauser role")]
+    fn test_previous_owner_loses_privileges() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), "common".to_string());
+        contract.propose_owner(accounts(1));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.accept_ownership();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.pause(None);
+    }
+
+    fn setup_registered() -> (VMContextBuilder, Contract) {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), "common".to_string());
+        testing_env!(context
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+        testing_env!(context
+            .attached_deposit(0)
+            .predecessor_account_id(accounts(0))
+            .build());
+        contract.set_sale_inventory(10.into());
+        (context, contract)
+    }
+
+    #[test]
+    #[should_panic(expected = "The sales feature is paused")]
+    fn test_buy_box_paused() {
+        let (mut context, mut contract) = setup_registered();
+        contract.pause(Some(Feature::Sales));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(10u128.pow(24))
+            .build());
+        contract.buy_box(accounts(1), 1.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "The transfers feature is paused")]
+    fn test_ft_transfer_paused() {
+        let (mut context, mut contract) = setup_registered();
+        contract.pause(Some(Feature::Transfers));
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_transfer(accounts(1), 1.into(), None);
+    }
+
+    #[test]
+    fn test_ft_burn() {
+        let (mut context, mut contract) = setup_registered();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.buy_box(accounts(1), 3.into());
+        contract.ft_burn(2.into(), Some("open_box".to_string()));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 2);
+        assert!(get_logs().iter().any(|log| log.contains("\"ft_burn\"")));
+    }
+
+    #[test]
+    #[should_panic(expected = "The account doesn't have enough balance")]
+    fn test_ft_burn_over_balance() {
+        let (mut context, mut contract) = setup_registered();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.ft_burn(1.into(), None);
+    }
+
+    #[test]
+    fn test_unpause_resumes_sales() {
+        let (mut context, mut contract) = setup_registered();
+        contract.pause(None);
+        contract.unpause(None);
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(10u128.pow(24))
+            .build());
+        contract.buy_box(accounts(1), 1.into());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1);
+    }
+
+    #[test]
+    fn test_buy_box_from_inventory() {
+        let (mut context, mut contract) = setup_registered();
+        assert_eq!(contract.get_sale_inventory().0, 10);
+        assert_eq!(contract.get_reserve().0, TOTAL_SUPPLY - 10);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.buy_box(accounts(1), 4.into());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 4);
+        assert_eq!(contract.get_sale_inventory().0, 6);
+        assert_eq!(contract.get_boxes_sold().0, 4);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.starts_with("EVENT_JSON:") && log.contains("\"ft_transfer\"")));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0)
+            .build());
+        contract.set_sale_inventory(2.into());
+        assert_eq!(contract.get_sale_inventory().0, 2);
+        assert_eq!(contract.get_reserve().0, TOTAL_SUPPLY - 6);
+        assert_eq!(contract.ft_balance_of(accounts(5)).0, TOTAL_SUPPLY - 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough boxes left in the sale inventory")]
+    fn test_boxes_sent_to_contract_not_for_sale() {
+        let (mut context, mut contract) = setup_registered();
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(1)
+            .build());
+        contract.withdraw_reserve(accounts(0), (TOTAL_SUPPLY - 10).into());
+        contract.ft_transfer(accounts(5), 5.into(), None);
+        assert_eq!(contract.get_sale_inventory().0, 10);
+        assert_eq!(contract.get_reserve().0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(5)).0, 15);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.buy_box(accounts(1), 11.into());
+    }
+
+    #[test]
+    fn test_sale_inventory_never_moves_owner_boxes() {
+        let (mut context, mut contract) = setup_registered();
+        testing_env!(context.attached_deposit(1).build());
+        contract.withdraw_reserve(accounts(0), 100.into());
+        contract.propose_owner(accounts(1));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(0)
+            .build());
+        contract.accept_ownership();
+
+        contract.set_sale_inventory(4.into());
+        assert_eq!(contract.get_reserve().0, TOTAL_SUPPLY - 104);
+        assert_eq!(contract.ft_balance_of(accounts(5)).0, TOTAL_SUPPLY - 100);
+        assert_eq!(contract.ft_balance_of(accounts(0)).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "The reserve only holds 1000000000 boxes")]
+    fn test_set_sale_inventory_above_reserve() {
+        let (_, mut contract) = setup_registered();
+        contract.set_sale_inventory((TOTAL_SUPPLY + 1).into());
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller is not owner.")]
+    fn test_withdraw_reserve_not_owner() {
+        let (mut context, mut contract) = setup_registered();
+        contract.grant_role(Role::Admin, accounts(1));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.withdraw_reserve(accounts(1), 1.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough boxes left in the sale inventory")]
+    fn test_buy_box_over_inventory() {
+        let (mut context, mut contract) = setup_registered();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.buy_box(accounts(1), 11.into());
+    }
+
+    #[test]
+    fn test_buy_box_registers_receiver() {
+        let (mut context, mut contract) = setup_registered();
+        let storage_cost: Balance = contract.storage_balance_bounds().min.into();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(storage_cost)
+            .build());
+        contract.buy_box(accounts(2), 1.into());
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 1);
+        assert!(contract.storage_balance_of(accounts(2)).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "Please deposit box price + storage")]
+    fn test_buy_box_registration_not_paid() {
+        let (mut context, mut contract) = setup_registered();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.buy_box(accounts(2), 1.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller is not owner.")]
+    fn test_set_sale_inventory_not_owner() {
+        let (mut context, mut contract) = setup_registered();
+        contract.grant_role(Role::Admin, accounts(1));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.set_sale_inventory(1.into());
+    }
+
+    #[test]
+    fn test_bundle_price() {
+        let (mut context, mut contract) = setup_registered();
+        contract.set_box_price(100.into());
+        contract.set_bundle_tiers(vec![
+            BundleTier {
+                min_amount: 5.into(),
+                unit_price: 80.into(),
+            },
+            BundleTier {
+                min_amount: 3.into(),
+                unit_price: 90.into(),
+            },
+        ]);
+        assert_eq!(
+            contract.get_box_price(2.into(), Some(accounts(1))).price.0,
+            200
+        );
+        assert_eq!(
+            contract.get_box_price(3.into(), Some(accounts(1))).price.0,
+            270
+        );
+        let quote = contract.get_box_price(6.into(), Some(accounts(1)));
+        assert_eq!(quote.unit_price.0, 80);
+        assert_eq!(quote.total.0, 480);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(480)
+            .build());
+        contract.buy_box(accounts(1), 6.into());
+        assert_eq!(contract.get_proceeds().collected.0, 480);
+    }
+
+    #[test]
+    #[should_panic(expected = "Please deposit box price + storage")]
+    fn test_buy_box_underpaid() {
+        let (mut context, mut contract) = setup_registered();
+        contract.set_box_price(100.into());
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(199)
+            .build());
+        contract.buy_box(accounts(1), 2.into());
+    }
+
+    #[test]
+    fn test_box_quote_includes_storage() {
+        let (mut context, mut contract) = setup_registered();
+        contract.set_box_price(100.into());
+        let storage_cost: Balance = contract.storage_balance_bounds().min.into();
+        let quote = contract.get_box_price(1.into(), Some(accounts(2)));
+        assert_eq!(quote.storage_cost.0, storage_cost);
+        assert_eq!(quote.total.0, storage_cost + 100);
+        assert_eq!(
+            contract.get_box_price(1.into(), None).storage_cost.0,
+            storage_cost
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(quote.total.0)
+            .build());
+        contract.buy_box(accounts(2), 1.into());
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 1);
+    }
+
+    #[test]
+    fn test_sale_window() {
+        let (mut context, mut contract) = setup_registered();
+        contract.set_sale_window(Some(OPEN_TIME), Some(CLOSE_TIME));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(OPEN_TIME)
+            .attached_deposit(1)
+            .build());
+        contract.buy_box(accounts(1), 1.into());
+        assert_eq!(contract.get_sale_config().close_time, Some(CLOSE_TIME));
+    }
+
+    #[test]
+    #[should_panic(expected = "Box sale has not started yet")]
+    fn test_sale_window_not_started() {
+        let (mut context, mut contract) = setup_registered();
+        contract.set_sale_window(Some(OPEN_TIME), None);
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(OPEN_TIME - 1)
+            .attached_deposit(1)
+            .build());
+        contract.buy_box(accounts(1), 1.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "Box sale has ended")]
+    fn test_sale_window_ended() {
+        let (mut context, mut contract) = setup_registered();
+        contract.set_sale_window(None, Some(CLOSE_TIME));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(CLOSE_TIME)
+            .attached_deposit(1)
+            .build());
+        contract.buy_box(accounts(1), 1.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "Sale times must be in nanoseconds")]
+    fn test_sale_window_in_seconds() {
+        let (_, mut contract) = setup_registered();
+        contract.set_sale_window(Some(OPEN_TIME / 1_000_000_000), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller is not owner.")]
+    fn test_set_box_price_not_owner() {
+        let (mut context, mut contract) = setup_registered();
+        contract.grant_role(Role::Admin, accounts(1));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.set_box_price(1.into());
+    }
+}
@@ -0,0 +1,228 @@
+use crate::*;
+
+/// Unit price that applies when buying at least `min_amount` boxes at once.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BundleTier {
+    pub min_amount: U128,
+    pub unit_price: U128,
+}
+
+/// Box sale settings, as returned by `get_sale_config`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SaleConfig {
+    pub unit_price: U128,
+    pub bundle_tiers: Vec<BundleTier>,
+    pub open_time: Option<Timestamp>,
+    pub close_time: Option<Timestamp>,
+}
+
+/// What `buy_box` charges for an amount of boxes, as returned by `get_box_price`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BoxQuote {
+    pub unit_price: U128,
+    pub price: U128,
+    /// Storage registration of the receiver, zero if it is already registered.
+    pub storage_cost: U128,
+    pub total: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sells `amount` boxes from the sale inventory to `receiver_id`. Unregistered receivers are
+    /// registered on the fly and the caller pays their storage on top of the box price.
+    #[payable]
+    pub fn buy_box(&mut self, receiver_id: ValidAccountId, amount: U128) {
+        self.pausable.assert_not_paused(Feature::Sales);
+        self.assert_sale_open();
+        let amount: Balance = amount.into();
+        assert!(amount > 0, "Must buy at least one box");
+        assert!(
+            amount <= self.sale_inventory,
+            "Not enough boxes left in the sale inventory"
+        );
+
+        let receiver_id: AccountId = receiver_id.into();
+        let mut storage_cost = 0;
+        if !self.token.accounts.contains_key(&receiver_id) {
+            self.token.internal_register_account(&receiver_id);
+            storage_cost =
+                Balance::from(self.token.account_storage_usage) * env::storage_byte_cost();
+        }
+
+        let price = self.internal_box_price(amount);
+        let required_cost = storage_cost + price;
+        let attached_deposit = env::attached_deposit();
+        assert!(
+            required_cost <= attached_deposit,
+            "Please deposit box price + storage: {}, excess deposit will be refund !",
+            required_cost
+        );
+
+        let inventory_id = env::current_account_id();
+        self.token
+            .internal_transfer(&inventory_id, &receiver_id, amount, None);
+        emit_ft_transfer(&inventory_id, &receiver_id, amount, Some("buy_box"));
+        self.sale_inventory -= amount;
+        self.boxes_sold += amount;
+        self.proceeds.collected += price;
+
+        let refund = attached_deposit - required_cost;
+        if refund > 1 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+    }
+
+    /// Sets how many boxes are for sale, moving the difference between the reserve and the
+    /// inventory. Both are held by the contract account, so no balance moves.
+    pub fn set_sale_inventory(&mut self, amount: U128) {
+        self.assert_owner();
+        let amount: Balance = amount.into();
+        let available = self.reserve + self.sale_inventory;
+        assert!(
+            amount <= available,
+            "The reserve only holds {} boxes",
+            available
+        );
+        self.reserve = available - amount;
+        self.sale_inventory = amount;
+    }
+
+    /// Sends boxes from the reserve to `receiver_id`, e.g. for giveaways. Only the owner can
+    /// withdraw.
+    #[payable]
+    pub fn withdraw_reserve(&mut self, receiver_id: ValidAccountId, amount: U128) {
+        assert_eq!(
+            env::attached_deposit(),
+            1,
+            "Requires attached deposit of exactly 1 yoctoNEAR",
+        );
+        self.assert_owner();
+        let amount: Balance = amount.into();
+        assert!(amount > 0, "The amount should be a positive number");
+        assert!(
+            amount <= self.reserve,
+            "The reserve only holds {} boxes",
+            self.reserve
+        );
+        let reserve_id = env::current_account_id();
+        self.token
+            .internal_transfer(&reserve_id, receiver_id.as_ref(), amount, None);
+        emit_ft_transfer(
+            &reserve_id,
+            receiver_id.as_ref(),
+            amount,
+            Some("withdraw_reserve"),
+        );
+        self.reserve -= amount;
+    }
+
+    pub fn set_box_price(&mut self, price: U128) {
+        self.assert_owner();
+        self.price = price.into();
+    }
+
+    /// Replaces the bundle tiers. The cheapest tier whose `min_amount` is reached applies.
+    pub fn set_bundle_tiers(&mut self, tiers: Vec<BundleTier>) {
+        self.assert_owner();
+        for tier in &tiers {
+            assert!(
+                tier.min_amount.0 > 1,
+                "Bundle tiers must start above one box"
+            );
+        }
+        self.bundle_tiers = tiers;
+    }
+
+    /// Limits box sales to `open_time` (inclusive) until `close_time` (exclusive), both in
+    /// nanoseconds. Either bound can be left open.
+    pub fn set_sale_window(&mut self, open_time: Option<Timestamp>, close_time: Option<Timestamp>) {
+        self.assert_owner();
+        assert!(
+            open_time
+                .iter()
+                .chain(close_time.iter())
+                .all(|time| *time >= MIN_SALE_TIMESTAMP),
+            "Sale times must be in nanoseconds"
+        );
+        if let (Some(open_time), Some(close_time)) = (open_time, close_time) {
+            assert!(
+                open_time < close_time,
+                "Open time must be before close time"
+            );
+        }
+        self.open_time = open_time;
+        self.close_time = close_time;
+    }
+
+    pub fn get_sale_config(&self) -> SaleConfig {
+        SaleConfig {
+            unit_price: U128(self.price),
+            bundle_tiers: self.bundle_tiers.clone(),
+            open_time: self.open_time,
+            close_time: self.close_time,
+        }
+    }
+
+    /// Quotes `buy_box` for `amount` boxes. Storage is included unless `receiver_id` is given
+    /// and already registered.
+    pub fn get_box_price(&self, amount: U128, receiver_id: Option<ValidAccountId>) -> BoxQuote {
+        let amount: Balance = amount.into();
+        let price = self.internal_box_price(amount);
+        let registered = receiver_id
+            .map(|receiver_id| self.token.accounts.contains_key(receiver_id.as_ref()))
+            .unwrap_or(false);
+        let storage_cost = if registered {
+            0
+        } else {
+            Balance::from(self.token.account_storage_usage) * env::storage_byte_cost()
+        };
+        BoxQuote {
+            unit_price: U128(self.internal_unit_price(amount)),
+            price: U128(price),
+            storage_cost: U128(storage_cost),
+            total: U128(price + storage_cost),
+        }
+    }
+
+    pub fn get_sale_inventory(&self) -> U128 {
+        U128(self.sale_inventory)
+    }
+
+    /// Boxes held by the contract account that are not for sale.
+    pub fn get_reserve(&self) -> U128 {
+        U128(self.reserve)
+    }
+
+    pub fn get_boxes_sold(&self) -> U128 {
+        U128(self.boxes_sold)
+    }
+}
+
+impl Contract {
+    fn assert_sale_open(&self) {
+        let now = env::block_timestamp();
+        if let Some(open_time) = self.open_time {
+            assert!(now >= open_time, "Box sale has not started yet");
+        }
+        if let Some(close_time) = self.close_time {
+            assert!(now < close_time, "Box sale has ended");
+        }
+    }
+
+    fn internal_unit_price(&self, amount: Balance) -> Balance {
+        self.bundle_tiers
+            .iter()
+            .filter(|tier| amount >= tier.min_amount.0)
+            .map(|tier| tier.unit_price.0)
+            .fold(self.price, Balance::min)
+    }
+
+    fn internal_box_price(&self, amount: Balance) -> Balance {
+        self.internal_unit_price(amount)
+            .checked_mul(amount)
+            .unwrap_or_else(|| env::panic(b"Box price overflow"))
+    }
+}
@@ -0,0 +1,60 @@
+use crate::*;
+
+/// Accounts of the Scity contracts a contract talks to.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractConfig {
+    pub box_contract_id: ValidAccountId,
+    pub land_contract_id: ValidAccountId,
+    pub company_contract_id: ValidAccountId,
+}
+
+impl ContractConfig {
+    /// Replaces the given contract accounts, leaving the others unchanged.
+    pub fn update(
+        &mut self,
+        box_contract_id: Option<ValidAccountId>,
+        land_contract_id: Option<ValidAccountId>,
+        company_contract_id: Option<ValidAccountId>,
+    ) {
+        for (name, field, account_id) in [
+            (
+                "box_contract_id",
+                &mut self.box_contract_id,
+                box_contract_id,
+            ),
+            (
+                "land_contract_id",
+                &mut self.land_contract_id,
+                land_contract_id,
+            ),
+            (
+                "company_contract_id",
+                &mut self.company_contract_id,
+                company_contract_id,
+            ),
+        ] {
+            if let Some(account_id) = account_id {
+                scity_access::emit_config_updated(
+                    name,
+                    field.as_ref(),
+                    account_id.as_ref(),
+                    &env::predecessor_account_id(),
+                );
+                *field = account_id;
+            }
+        }
+    }
+}
+
+pub trait ContractConfigCore {
+    /// Replaces the given contract accounts, leaving the others unchanged.
+    fn update_config(
+        &mut self,
+        box_contract_id: Option<ValidAccountId>,
+        land_contract_id: Option<ValidAccountId>,
+        company_contract_id: Option<ValidAccountId>,
+    );
+
+    fn get_config(&self) -> ContractConfig;
+}
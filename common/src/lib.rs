@@ -0,0 +1,23 @@
+//! Contract building blocks shared by the Scity contracts.
+//!
+//! [`ProceedsLedger`] tracks the sale revenue of a contract and [`TreasuryConfig`] how it is split
+//! when withdrawn. Contracts keep both as fields and expose the treasury methods with the
+//! [`impl_treasury!`] macro. [`ContractConfig`] holds the accounts of the other Scity contracts,
+//! exposed with [`impl_contract_config!`].
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{ValidAccountId, U128};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, ext_contract, AccountId, Balance, Gas, PromiseOrValue, Timestamp};
+
+pub use crate::config::*;
+pub use crate::treasury::*;
+#[doc(hidden)]
+pub use scity_access;
+
+mod config;
+mod macros;
+mod treasury;
+
+/// 2001-09-09 in nanoseconds. Sale times below this were almost certainly given in seconds or
+/// milliseconds.
+pub const MIN_SALE_TIMESTAMP: Timestamp = 1_000_000_000_000_000_000;
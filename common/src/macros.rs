@@ -0,0 +1,133 @@
+/// Exposes the treasury over the contract's [`ProceedsLedger`](crate::ProceedsLedger) and
+/// optional [`TreasuryConfig`](crate::TreasuryConfig) fields. Only the owner configures the
+/// treasury and withdraws.
+///
+/// The contract module must have `TreasuryCore` in scope and an `assert_owner` helper.
+#[macro_export]
+macro_rules! impl_treasury {
+    ($contract: ident, $proceeds: ident, $treasury: ident) => {
+        #[near_sdk::near_bindgen]
+        impl $crate::TreasuryCore for $contract {
+            fn set_treasury(&mut self, treasury: $crate::TreasuryConfig) {
+                self.assert_owner();
+                treasury.assert_valid();
+                assert!(
+                    near_sdk::env::is_valid_account_id(treasury.team_account_id.as_bytes())
+                        && near_sdk::env::is_valid_account_id(treasury.dao_account_id.as_bytes()),
+                    "Invalid treasury account id"
+                );
+                self.$treasury = Some(treasury);
+            }
+
+            fn withdraw_proceeds(
+                &mut self,
+            ) -> near_sdk::PromiseOrValue<near_sdk::json_types::U128> {
+                self.assert_owner();
+                let treasury = self.$treasury.clone().expect("Treasury is not configured");
+                let amount = self.$proceeds.available();
+                assert!(amount > 0, "No proceeds to withdraw");
+
+                let split = treasury.split(amount);
+                self.$proceeds.withdrawn += split.team_amount + split.dao_amount;
+                self.$proceeds.burned += split.burn_amount;
+
+                near_sdk::env::log(
+                    format!(
+                        "Withdrew {} proceeds: {} to @{}, {} to @{}, {} burned",
+                        amount,
+                        split.team_amount,
+                        treasury.team_account_id,
+                        split.dao_amount,
+                        treasury.dao_account_id,
+                        split.burn_amount
+                    )
+                    .as_bytes(),
+                );
+                let payouts: Vec<(near_sdk::AccountId, near_sdk::json_types::U128)> = vec![
+                    (treasury.team_account_id, split.team_amount),
+                    (treasury.dao_account_id, split.dao_amount),
+                ]
+                .into_iter()
+                .filter(|(_, amount)| *amount > 0)
+                .map(|(account_id, amount)| (account_id, near_sdk::json_types::U128(amount)))
+                .collect();
+                let transfers = payouts
+                    .iter()
+                    .map(|(account_id, amount)| {
+                        near_sdk::Promise::new(account_id.clone()).transfer(amount.0)
+                    })
+                    .reduce(|joined, transfer| joined.and(transfer));
+                match transfers {
+                    Some(transfers) => near_sdk::PromiseOrValue::Promise(transfers.then(
+                        $crate::ext_treasury::resolve_withdraw_proceeds(
+                            payouts,
+                            &near_sdk::env::current_account_id(),
+                            0,
+                            $crate::GAS_FOR_RESOLVE_WITHDRAW,
+                        ),
+                    )),
+                    None => near_sdk::PromiseOrValue::Value(near_sdk::json_types::U128(0)),
+                }
+            }
+
+            #[private]
+            fn resolve_withdraw_proceeds(
+                &mut self,
+                payouts: Vec<(near_sdk::AccountId, near_sdk::json_types::U128)>,
+            ) -> near_sdk::json_types::U128 {
+                let mut paid = 0;
+                for (i, (account_id, amount)) in payouts.into_iter().enumerate() {
+                    match near_sdk::env::promise_result(i as u64) {
+                        near_sdk::PromiseResult::Successful(_) => paid += amount.0,
+                        _ => {
+                            self.$proceeds.withdrawn -= amount.0;
+                            near_sdk::env::log(
+                                format!(
+                                    "Proceeds payout of {} to @{} failed and was restored",
+                                    amount.0, account_id
+                                )
+                                .as_bytes(),
+                            );
+                        }
+                    }
+                }
+                near_sdk::json_types::U128(paid)
+            }
+
+            fn get_treasury(&self) -> Option<$crate::TreasuryConfig> {
+                self.$treasury.clone()
+            }
+
+            fn get_proceeds(&self) -> $crate::ProceedsView {
+                self.$proceeds.to_view()
+            }
+        }
+    };
+}
+
+/// Exposes the contract's [`ContractConfig`](crate::ContractConfig) field, updated by admins.
+///
+/// The contract module must have `ContractConfigCore` in scope and
+/// `scity_access::impl_access_control!` on the same contract.
+#[macro_export]
+macro_rules! impl_contract_config {
+    ($contract: ident, $config: ident) => {
+        #[near_sdk::near_bindgen]
+        impl $crate::ContractConfigCore for $contract {
+            fn update_config(
+                &mut self,
+                box_contract_id: Option<near_sdk::json_types::ValidAccountId>,
+                land_contract_id: Option<near_sdk::json_types::ValidAccountId>,
+                company_contract_id: Option<near_sdk::json_types::ValidAccountId>,
+            ) {
+                self.assert_role($crate::scity_access::Role::Admin);
+                self.$config
+                    .update(box_contract_id, land_contract_id, company_contract_id);
+            }
+
+            fn get_config(&self) -> $crate::ContractConfig {
+                self.$config.clone()
+            }
+        }
+    };
+}
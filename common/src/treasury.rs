@@ -0,0 +1,125 @@
+use crate::*;
+
+pub const GAS_FOR_RESOLVE_WITHDRAW: Gas = 10_000_000_000_000;
+
+/// Treasury splits are expressed in basis points, the three shares must add up to `10_000`.
+pub const SPLIT_BASE: u32 = 10_000;
+
+/// Where withdrawn sale proceeds go.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TreasuryConfig {
+    pub team_account_id: AccountId,
+    pub dao_account_id: AccountId,
+    pub team_share: u32,
+    pub dao_share: u32,
+    /// Share that is never paid out and stays locked on the contract.
+    pub burn_share: u32,
+}
+
+/// Sale revenue of the whole contract.
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct ProceedsLedger {
+    pub collected: Balance,
+    pub withdrawn: Balance,
+    pub burned: Balance,
+}
+
+/// Proceeds as returned by `get_proceeds`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProceedsView {
+    pub collected: U128,
+    pub withdrawn: U128,
+    pub burned: U128,
+    pub available: U128,
+}
+
+/// Amounts a withdrawal takes from the ledger.
+#[derive(Debug, PartialEq)]
+pub struct ProceedsSplit {
+    pub team_amount: Balance,
+    pub dao_amount: Balance,
+    pub burn_amount: Balance,
+}
+
+impl TreasuryConfig {
+    pub fn assert_valid(&self) {
+        assert!(
+            self.team_share + self.dao_share + self.burn_share == SPLIT_BASE,
+            "Treasury shares must add up to {}",
+            SPLIT_BASE
+        );
+    }
+
+    /// Splits `amount` by the shares, the team receives the rounding remainder.
+    pub fn split(&self, amount: Balance) -> ProceedsSplit {
+        let dao_amount = amount * Balance::from(self.dao_share) / Balance::from(SPLIT_BASE);
+        let burn_amount = amount * Balance::from(self.burn_share) / Balance::from(SPLIT_BASE);
+        ProceedsSplit {
+            team_amount: amount - dao_amount - burn_amount,
+            dao_amount,
+            burn_amount,
+        }
+    }
+}
+
+impl ProceedsLedger {
+    pub fn available(&self) -> Balance {
+        self.collected - self.withdrawn - self.burned
+    }
+
+    pub fn to_view(&self) -> ProceedsView {
+        ProceedsView {
+            collected: U128(self.collected),
+            withdrawn: U128(self.withdrawn),
+            burned: U128(self.burned),
+            available: U128(self.available()),
+        }
+    }
+}
+
+#[ext_contract(ext_treasury)]
+pub trait TreasuryResolver {
+    fn resolve_withdraw_proceeds(&mut self, payouts: Vec<(AccountId, U128)>) -> U128;
+}
+
+pub trait TreasuryCore {
+    fn set_treasury(&mut self, treasury: TreasuryConfig);
+
+    /// Pays out all available proceeds to the team and DAO accounts and burns the rest of the
+    /// split. Resolves to the amount paid out.
+    fn withdraw_proceeds(&mut self) -> PromiseOrValue<U128>;
+
+    /// Puts the payouts that failed back into the available proceeds, and returns the amount
+    /// paid.
+    fn resolve_withdraw_proceeds(&mut self, payouts: Vec<(AccountId, U128)>) -> U128;
+
+    fn get_treasury(&self) -> Option<TreasuryConfig>;
+
+    fn get_proceeds(&self) -> ProceedsView;
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let treasury = TreasuryConfig {
+            team_account_id: "team".to_string(),
+            dao_account_id: "dao".to_string(),
+            team_share: 5_000,
+            dao_share: 3_333,
+            burn_share: 1_667,
+        };
+        assert_eq!(
+            treasury.split(10),
+            ProceedsSplit {
+                team_amount: 6,
+                dao_amount: 3,
+                burn_amount: 1,
+            }
+        );
+    }
+}
@@ -1,6 +1,6 @@
-#!/bin/bash
-set -e
-
-RUSTFLAGS='-C link-arg=-s' cargo build --target wasm32-unknown-unknown --release
-mkdir -p ../../out
-cp target/wasm32-unknown-unknown/release/*.wasm ./res/main.wasm
+#!/bin/bash
+set -e
+
+RUSTFLAGS='-C link-arg=-s' cargo build -p scity-company --target wasm32-unknown-unknown --release
+mkdir -p ../../out
+cp ../target/wasm32-unknown-unknown/release/scity_company.wasm ./res/main.wasm
@@ -0,0 +1,76 @@
+use crate::*;
+
+/// A kind of company boxes can open into, e.g. finance, tech, energy or retail.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CompanyType {
+    pub company_type: String,
+    /// Relative chance of rolling this type among the catalog.
+    pub weight: u32,
+    pub mining_power: StatRange,
+    pub mining_efficiency: StatRange,
+}
+
+/// Stats rolled for a single company.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompanyStats {
+    pub company_type: String,
+    pub level: u8,
+    pub rare: String,
+    pub mining_power: u64,
+    pub mining_efficiency: u64,
+}
+
+/// Catalog used until the owner configures one, matching the stats of the first companies.
+pub(crate) fn default_company_types() -> Vec<CompanyType> {
+    vec![CompanyType {
+        company_type: String::from("finance"),
+        weight: 1,
+        mining_power: StatRange { min: 10, max: 10 },
+        mining_efficiency: StatRange { min: 20, max: 20 },
+    }]
+}
+
+pub(crate) fn assert_valid_company_types(company_types: &[CompanyType]) {
+    assert!(
+        company_types
+            .iter()
+            .any(|company_type| company_type.weight > 0),
+        "Company catalog must have a positive weight"
+    );
+    for (i, company_type) in company_types.iter().enumerate() {
+        company_type.mining_power.assert_valid();
+        company_type.mining_efficiency.assert_valid();
+        assert!(
+            company_types[..i]
+                .iter()
+                .all(|other| other.company_type != company_type.company_type),
+            "Duplicate company type {}",
+            company_type.company_type
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Replaces the catalog companies are rolled from when boxes are opened. Counts of
+    /// companies already minted per type are kept.
+    pub fn set_company_types(&mut self, company_types: Vec<CompanyType>) {
+        self.assert_role(Role::Admin);
+        assert_valid_company_types(&company_types);
+        for box_type in &self.box_types {
+            box_type.loot_table.assert_valid(&company_types);
+        }
+        self.company_types = company_types;
+    }
+
+    pub fn get_company_types(&self) -> Vec<CompanyType> {
+        self.company_types.clone()
+    }
+
+    /// Number of companies opened from boxes per type, including types no longer in the
+    /// catalog.
+    pub fn get_minted_by_type(&self) -> HashMap<String, u64> {
+        self.minted_by_type.clone()
+    }
+}
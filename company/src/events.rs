@@ -0,0 +1,32 @@
+use near_sdk::serde_json::{json, Value};
+use near_sdk::{env, AccountId};
+
+/// Logs a NEP-297 event of the NEP-171 standard.
+fn emit(event: &str, mut data: Value, memo: Option<&str>) {
+    if let Some(memo) = memo {
+        data["memo"] = json!(memo);
+    }
+    let event = json!({
+        "standard": "nep171",
+        "version": "1.0.0",
+        "event": event,
+        "data": [data],
+    });
+    env::log(format!("EVENT_JSON:{}", event).as_bytes());
+}
+
+pub(crate) fn emit_nft_mint(owner_id: &AccountId, token_ids: &[String], memo: Option<&str>) {
+    emit(
+        "nft_mint",
+        json!({ "owner_id": owner_id, "token_ids": token_ids }),
+        memo,
+    );
+}
+
+pub(crate) fn emit_nft_burn(owner_id: &AccountId, token_ids: &[String], memo: Option<&str>) {
+    emit(
+        "nft_burn",
+        json!({ "owner_id": owner_id, "token_ids": token_ids }),
+        memo,
+    );
+}
@@ -0,0 +1,157 @@
+use crate::*;
+
+/// Turns several companies of the same type into one stronger company.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FusionRecipe {
+    pub name: String,
+    /// Number of companies burned per fusion.
+    pub input_count: u8,
+    /// Level every fused company must have.
+    pub input_level: u8,
+    /// Rarity every fused company must have, any if `None`.
+    pub input_rare: Option<String>,
+    pub output_level: u8,
+    /// Rarity of the new company, the rarity of the first fused company if `None`.
+    pub output_rare: Option<String>,
+    /// Applied to the summed mining stats of the fused companies, in basis points of
+    /// [`MULTIPLIER_BASE`].
+    pub multiplier: u32,
+}
+
+impl FusionRecipe {
+    fn matches(&self, inputs: &[TokenMetadata]) -> bool {
+        inputs.len() == usize::from(self.input_count)
+            && inputs.iter().all(|input| {
+                input.level.unwrap_or(1) == self.input_level
+                    && (self.input_rare.is_none() || input.rare == self.input_rare)
+            })
+    }
+}
+
+pub(crate) fn assert_valid_fusion_recipes(recipes: &[FusionRecipe]) {
+    for (i, recipe) in recipes.iter().enumerate() {
+        assert!(
+            recipe.input_count >= 2,
+            "Recipes must fuse at least 2 companies"
+        );
+        assert!(
+            recipe.input_level >= 1 && recipe.output_level >= 1,
+            "Recipe levels must be 1 or above"
+        );
+        assert!(recipe.multiplier > 0, "Recipe multiplier must be positive");
+        assert!(
+            recipes[..i].iter().all(|other| other.name != recipe.name),
+            "Duplicate fusion recipe {}",
+            recipe.name
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Burns the caller's companies and mints them one new company of the same type, using the
+    /// first recipe that matches them. Storage freed by the burn pays for the new company, and
+    /// the storage of the burned companies' approvals is refunded.
+    #[payable]
+    pub fn fuse_companies(&mut self, token_ids: Vec<TokenId>) -> TokenId {
+        assert_one_yocto();
+        self.pausable.assert_not_paused(Feature::Fusion);
+        let owner_id = env::predecessor_account_id();
+        let mut inputs = Vec::with_capacity(token_ids.len());
+        for (i, token_id) in token_ids.iter().enumerate() {
+            assert!(
+                !token_ids[..i].contains(token_id),
+                "Cannot fuse company {} twice",
+                token_id
+            );
+            let token = self
+                .tokens
+                .tokens_by_id
+                .get(token_id)
+                .expect("Token not found");
+            assert_eq!(
+                token.owner_id, owner_id,
+                "Only the owner can fuse company {}",
+                token_id
+            );
+            inputs.push(self.tokens.token_metadata_by_id.get(token_id).unwrap());
+        }
+        let company_type = inputs
+            .first()
+            .and_then(|input| input.company_type.clone())
+            .expect("No companies to fuse");
+        assert!(
+            inputs
+                .iter()
+                .all(|input| input.company_type.as_ref() == Some(&company_type)),
+            "Only companies of the same type can be fused"
+        );
+        let recipe = self
+            .fusion_recipes
+            .iter()
+            .find(|recipe| recipe.matches(&inputs))
+            .cloned()
+            .expect("No fusion recipe matches these companies");
+
+        let mut approved_account_ids = Vec::new();
+        for token_id in &token_ids {
+            let token = self.tokens.internal_burn(token_id);
+            approved_account_ids.extend(token.approved_account_ids.into_keys());
+            self.upgrade_history.remove(token_id);
+        }
+        emit_nft_burn(&owner_id, &token_ids, Some(&recipe.name));
+        if !approved_account_ids.is_empty() {
+            refund_approved_account_ids_iter(owner_id.clone(), approved_account_ids.iter());
+        }
+
+        let combine = |stat: fn(&TokenMetadata) -> Option<u64>| {
+            let total = inputs
+                .iter()
+                .map(|input| stat(input).unwrap_or(0))
+                .fold(0, u64::saturating_add);
+            apply_multiplier(total, recipe.multiplier)
+        };
+        let new_name = String::from("Company#") + &self.total.to_string();
+        let token_id = hex::encode(env::sha256(new_name.as_bytes()));
+        let token = TokenMetadata {
+            title: Some(new_name.clone()),
+            description: Some(new_name),
+            media: None,
+            media_hash: None,
+            copies: Some(1),
+            issued_at: Some(env::block_timestamp()),
+            level: Some(recipe.output_level),
+            company_type: Some(company_type),
+            rare: recipe
+                .output_rare
+                .clone()
+                .or_else(|| inputs[0].rare.clone()),
+            mining_power: Some(combine(|input| input.mining_power)),
+            mining_efficiency: Some(combine(|input| input.mining_efficiency)),
+        };
+        self.total += 1;
+        self.internal_nft_mint(
+            Some(token_id.clone()),
+            token,
+            Some(owner_id.clone().try_into().unwrap()),
+        );
+        emit_nft_mint(
+            &owner_id,
+            std::slice::from_ref(&token_id),
+            Some(&recipe.name),
+        );
+        token_id
+    }
+
+    /// Replaces the fusion recipes. Companies are fused with the first recipe they match.
+    pub fn set_fusion_recipes(&mut self, recipes: Vec<FusionRecipe>) {
+        self.assert_role(Role::Admin);
+        assert_valid_fusion_recipes(&recipes);
+        self.fusion_recipes = recipes;
+    }
+
+    pub fn get_fusion_recipes(&self) -> Vec<FusionRecipe> {
+        self.fusion_recipes.clone()
+    }
+}
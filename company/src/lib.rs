@@ -1,183 +1,1122 @@
-use std::cmp::min;
-use std::collections::HashMap;
-use std::convert::TryFrom;
-
-use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
-use near_sdk::json_types::{Base64VecU8, ValidAccountId, U128, U64};
-use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{
-    env, ext_contract, near_bindgen, AccountId, Balance, CryptoHash, Gas, PanicOnDefault, Promise,
-    StorageUsage,
-};
-
-pub use crate::enumerable::*;
-use crate::internal::*;
-pub use crate::metadata::*;
-pub use crate::mint::*;
-pub use crate::nft_core::*;
-pub use crate::token::*;
-
-mod enumerable;
-mod internal;
-mod metadata;
-mod mint;
-mod nft_core;
-mod token;
-
-const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
-const PREPARE_GAS: Gas = 1_500_000_000_000_0;
-const BOX_CONTRACT: &str = "box.nft-test.testnet";
-
-near_sdk::setup_alloc!();
-
-#[near_bindgen]
-#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
-pub struct Contract {
-    pub tokens_per_owner: LookupMap<AccountId, UnorderedSet<TokenId>>,
-
-    pub tokens_by_id: LookupMap<TokenId, Token>,
-
-    pub token_metadata_by_id: UnorderedMap<TokenId, TokenMetadata>,
-
-    pub owner_id: AccountId,
-
-    /// The storage size in bytes for one account.
-    pub extra_storage_in_bytes_per_token: StorageUsage,
-
-    pub metadata: LazyOption<NFTMetadata>,
-
-    pub total: u64,
-}
-
-/// Helper structure to for keys of the persistent collections.
-#[derive(BorshSerialize)]
-pub enum StorageKey {
-    TokensPerOwner,
-    TokenPerOwnerInner { account_id_hash: CryptoHash },
-    TokensById,
-    TokenMetadataById,
-    AreaMetadataById,
-    NftMetadata,
-    TokensPerType,
-    TokensPerTypeInner { token_type_hash: CryptoHash },
-    TokenTypesLocked,
-}
-
-#[near_bindgen]
-impl Contract {
-    #[init]
-    pub fn new_default_meta(owner_id: ValidAccountId) -> Self {
-        Self::new(
-            owner_id,
-            NFTMetadata {
-                spec: "nft-1.0.0".to_string(),
-                name: "Box Scity".to_string(),
-                symbol: "Land".to_string(),
-                icon: Some(DATA_IMAGE_SVG_NEAR_ICON.to_string()),
-                base_uri: None,
-                reference: None,
-                reference_hash: None,
-            },
-        )
-    }
-    #[init]
-    pub fn new(owner_id: ValidAccountId, metadata: NFTMetadata) -> Self {
-        let mut this = Self {
-            tokens_per_owner: LookupMap::new(StorageKey::TokensPerOwner.try_to_vec().unwrap()),
-            tokens_by_id: LookupMap::new(StorageKey::TokensById.try_to_vec().unwrap()),
-            token_metadata_by_id: UnorderedMap::new(
-                StorageKey::TokenMetadataById.try_to_vec().unwrap(),
-            ),
-            owner_id: owner_id.into(),
-            extra_storage_in_bytes_per_token: 0,
-            metadata: LazyOption::new(
-                StorageKey::NftMetadata.try_to_vec().unwrap(),
-                Some(&metadata),
-            ),
-            total: 0,
-        };
-
-        this.measure_min_token_storage_cost();
-
-        this
-    }
-
-    fn measure_min_token_storage_cost(&mut self) {
-        let initial_storage_usage = env::storage_usage();
-        let tmp_account_id = "a".repeat(64);
-        let u = UnorderedSet::new(
-            StorageKey::TokenPerOwnerInner {
-                account_id_hash: hash_account_id(&tmp_account_id),
-            }
-            .try_to_vec()
-            .unwrap(),
-        );
-        self.tokens_per_owner.insert(&tmp_account_id, &u);
-
-        let tokens_per_owner_entry_in_bytes = env::storage_usage() - initial_storage_usage;
-        let owner_id_extra_cost_in_bytes = (tmp_account_id.len() - self.owner_id.len()) as u64;
-
-        self.extra_storage_in_bytes_per_token =
-            tokens_per_owner_entry_in_bytes + owner_id_extra_cost_in_bytes;
-
-        self.tokens_per_owner.remove(&tmp_account_id);
-    }
-
-    #[payable]
-    pub fn open_box(&mut self) {
-        let new_name = String::from("Company#") + &self.total.to_string();
-        let token_id = hex::encode(&env::sha256(new_name.as_bytes()));
-
-        let token: TokenMetadata = TokenMetadata {
-            title: Some(String::from(new_name.clone())),
-            description: Some(String::from(new_name)),
-            media: None,
-            media_hash: None,
-            copies: Some(1),
-            issued_at: Some(env::block_timestamp()),
-            level: Some(1),
-            company_type: Some(String::from("finance")),
-            mining_power: Some(10),
-            mining_efficiency: Some(20),
-        };
-
-        self.total = self.total + 1;
-
-        ext_box::transfer_box_to_owner(
-            BOX_CONTRACT.to_string(),
-            String::from("1"),
-            Some(String::from("Openbox")),
-            &"ft.nft-test.testnet",
-            1,
-            PREPARE_GAS,
-        );
-        self.nft_mint(
-            Some(token_id),
-            token,
-            Some(ValidAccountId::try_from(env::predecessor_account_id()).unwrap()),
-        );
-    }
-
-    pub fn get_companies_by_owner(&self, owner_id: AccountId) -> Vec<TokenMetadata> {
-        let token_ids = self
-            .tokens_per_owner
-            .get(&owner_id)
-            .unwrap_or_else(|| UnorderedSet::new(b"".to_vec()));
-        token_ids
-            .iter()
-            .map(|token_id| self.token_metadata_by_id.get(&token_id).unwrap())
-            .collect()
-    }
-}
-
-#[ext_contract(ext_box)]
-trait BoxContract {
-    fn transfer_box_to_owner(
-        &mut self,
-        receiver_id: AccountId,
-        amount: String,
-        memo: Option<String>,
-    );
-    fn ft_balance_of(&self, account_id: AccountId) -> U128;
-}
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LazyOption, LookupMap};
+use near_sdk::json_types::{Base64VecU8, ValidAccountId, U128};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{
+    env, ext_contract, near_bindgen, serde_json, AccountId, Balance, Gas, PanicOnDefault, Promise,
+    PromiseOrValue, StorageUsage, Timestamp,
+};
+
+pub use crate::catalog::*;
+use crate::events::*;
+pub use crate::fuse::*;
+use crate::internal::*;
+pub use crate::loot::*;
+pub use crate::metadata::*;
+pub use crate::upgrade::*;
+pub use scity_access::*;
+pub use scity_common::*;
+pub use scity_nft_core::*;
+
+mod catalog;
+mod events;
+mod fuse;
+mod internal;
+mod loot;
+mod metadata;
+mod mint;
+mod upgrade;
+
+const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
+const PREPARE_GAS: Gas = 15_000_000_000_000;
+/// Keeps the minting in `ft_on_transfer` within its gas budget.
+const MAX_BOXES_PER_OPEN: u64 = 10;
+
+near_sdk::setup_alloc!();
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    pub tokens: NonFungibleToken<TokenMetadata>,
+
+    pub owner_id: AccountId,
+
+    /// Account proposed as the next owner, until it accepts.
+    pub pending_owner_id: Option<AccountId>,
+
+    pub access: AccessControl,
+
+    pub pausable: Pausable,
+
+    pub metadata: LazyOption<NFTMetadata>,
+
+    pub config: ContractConfig,
+
+    /// Types companies are rolled from when boxes are opened.
+    pub company_types: Vec<CompanyType>,
+
+    /// Box tiers that can be opened, with what they open into.
+    pub box_types: Vec<BoxType>,
+
+    /// Companies opened from boxes per type.
+    pub minted_by_type: HashMap<String, u64>,
+
+    pub upgrade_config: Option<UpgradeConfig>,
+
+    /// Upgrades applied to each company, oldest first.
+    pub upgrade_history: LookupMap<TokenId, Vec<UpgradeRecord>>,
+
+    /// NEAR deposited by each account to pay the storage of its upgrades paid in tokens.
+    pub upgrade_storage_deposits: LookupMap<AccountId, Balance>,
+
+    pub fusion_recipes: Vec<FusionRecipe>,
+
+    /// Upgrades paid in NEAR. Token payments are burned instead.
+    pub proceeds: ProceedsLedger,
+
+    pub treasury: Option<TreasuryConfig>,
+
+    pub total: u64,
+}
+
+/// Helper structure to for keys of the persistent collections.
+#[derive(BorshSerialize)]
+pub enum StorageKey {
+    NonFungibleToken,
+    NftMetadata,
+    AccessControl,
+    UpgradeHistory,
+    UpgradeStorageDeposits,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new_default_meta(owner_id: ValidAccountId, config: ContractConfig) -> Self {
+        Self::new(
+            owner_id,
+            NFTMetadata {
+                spec: "nft-1.0.0".to_string(),
+                name: "Box Scity".to_string(),
+                symbol: "Land".to_string(),
+                icon: Some(DATA_IMAGE_SVG_NEAR_ICON.to_string()),
+                base_uri: None,
+                reference: None,
+                reference_hash: None,
+            },
+            config,
+        )
+    }
+    #[init]
+    pub fn new(owner_id: ValidAccountId, metadata: NFTMetadata, config: ContractConfig) -> Self {
+        let owner_id: AccountId = owner_id.into();
+        Self {
+            tokens: NonFungibleToken::new(
+                StorageKey::NonFungibleToken.try_to_vec().unwrap(),
+                &owner_id,
+            ),
+            owner_id,
+            pending_owner_id: None,
+            access: AccessControl::new(StorageKey::AccessControl.try_to_vec().unwrap()),
+            pausable: Pausable::default(),
+            metadata: LazyOption::new(
+                StorageKey::NftMetadata.try_to_vec().unwrap(),
+                Some(&metadata),
+            ),
+            config,
+            company_types: default_company_types(),
+            box_types: default_box_types(),
+            minted_by_type: HashMap::new(),
+            upgrade_config: None,
+            upgrade_history: LookupMap::new(StorageKey::UpgradeHistory.try_to_vec().unwrap()),
+            upgrade_storage_deposits: LookupMap::new(
+                StorageKey::UpgradeStorageDeposits.try_to_vec().unwrap(),
+            ),
+            fusion_recipes: Vec::new(),
+            proceeds: ProceedsLedger::default(),
+            treasury: None,
+            total: 0,
+        }
+    }
+
+    /// Opens SBOX boxes sent with `ft_transfer_call` on the contract of a box tier, minting one
+    /// company to the sender per box from the tier's loot table and burning the boxes
+    /// afterwards. If minting panics, the box contract refunds every box to the sender.
+    /// Storage of the new companies is paid by this contract.
+    ///
+    /// Tokens sent with a [`TransferMessage`] pay for that action instead, and whatever is
+    /// sent above its cost is refunded. The storage the action uses is taken from the sender's
+    /// `deposit_upgrade_storage` balance.
+    pub fn ft_on_transfer(
+        &mut self,
+        sender_id: ValidAccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        if !msg.is_empty() {
+            let token_contract_id = env::predecessor_account_id();
+            let message: TransferMessage =
+                serde_json::from_str(&msg).expect("Invalid transfer message");
+            let initial_storage_usage = env::storage_usage();
+            let (cost, memo) = match message {
+                TransferMessage::UpgradeCompany { token_id } => (
+                    self.internal_upgrade_company(
+                        sender_id.as_ref(),
+                        &token_id,
+                        &UpgradeCurrency::Token(token_contract_id.clone().try_into().unwrap()),
+                    ),
+                    "upgrade_company",
+                ),
+            };
+            assert!(
+                cost <= amount.0,
+                "Must send {} tokens to cover the price",
+                cost
+            );
+            let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+            self.internal_charge_upgrade_storage(sender_id.as_ref(), storage_used);
+            // Payments are burned, the excess is refunded by the token contract.
+            if cost > 0 {
+                ext_box::ft_burn(
+                    U128(cost),
+                    Some(String::from(memo)),
+                    &token_contract_id,
+                    1,
+                    PREPARE_GAS,
+                );
+            }
+            return PromiseOrValue::Value(U128(amount.0 - cost));
+        }
+
+        let box_contract_id = env::predecessor_account_id();
+        let box_type = self
+            .internal_box_type_from(&box_contract_id)
+            .expect("Only SBOX boxes can be opened");
+        self.pausable.assert_not_paused(Feature::BoxOpening);
+        let amount: u64 = amount.0.try_into().unwrap_or(u64::MAX);
+        assert!(
+            amount > 0 && amount <= MAX_BOXES_PER_OPEN,
+            "Can open between 1 and {} boxes at once",
+            MAX_BOXES_PER_OPEN
+        );
+        let seed = env::random_seed();
+        let token_ids: Vec<TokenId> = (0..amount)
+            .map(|_| self.internal_mint_company(sender_id.clone(), &box_type.loot_table, &seed))
+            .collect();
+        emit_nft_mint(sender_id.as_ref(), &token_ids, Some("open_box"));
+        env::log(
+            format!(
+                "Opened {} {} boxes for @{}",
+                amount,
+                box_type.box_type,
+                sender_id.as_ref()
+            )
+            .as_bytes(),
+        );
+
+        ext_box::ft_burn(
+            U128(Balance::from(amount)),
+            Some(String::from("open_box")),
+            &box_contract_id,
+            1,
+            PREPARE_GAS,
+        );
+        PromiseOrValue::Value(U128(0))
+    }
+
+    pub fn get_companies_by_owner(&self, owner_id: AccountId) -> Vec<TokenMetadata> {
+        self.tokens.token_metadata_for_owner(&owner_id)
+    }
+}
+
+/// Message of an `ft_transfer_call` paying for something other than opening boxes.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum TransferMessage {
+    UpgradeCompany { token_id: TokenId },
+}
+
+/// Burn entrypoint of the SBOX contracts and the SCITY token.
+#[ext_contract(ext_box)]
+trait BoxContract {
+    fn ft_burn(&mut self, amount: U128, memo: Option<String>);
+}
+
+scity_nft_core::impl_non_fungible_token_core!(
+    Contract,
+    tokens,
+    TokenMetadata,
+    assert_transfers_not_paused
+);
+scity_nft_core::impl_non_fungible_token_enumeration!(Contract, tokens, TokenMetadata);
+scity_access::impl_access_control!(Contract, access, owner_id);
+scity_access::impl_ownership!(Contract, owner_id, pending_owner_id);
+scity_access::impl_pausable!(Contract, pausable);
+scity_common::impl_contract_config!(Contract, config);
+scity_common::impl_treasury!(Contract, proceeds, treasury);
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    const MINT_DEPOSIT: u128 = 20_000_000_000_000_000_000_000;
+
+    fn get_context(predecessor_account_id: ValidAccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id);
+        builder
+    }
+
+    fn box_contract_id() -> ValidAccountId {
+        ValidAccountId::try_from("box.scity.testnet").unwrap()
+    }
+
+    fn config() -> ContractConfig {
+        ContractConfig {
+            box_contract_id: box_contract_id(),
+            land_contract_id: ValidAccountId::try_from("land.scity.testnet").unwrap(),
+            company_contract_id: accounts(0),
+        }
+    }
+
+    fn company_metadata(title: &str) -> TokenMetadata {
+        TokenMetadata {
+            title: Some(title.to_string()),
+            description: None,
+            media: None,
+            media_hash: None,
+            copies: Some(1),
+            issued_at: None,
+            level: Some(1),
+            company_type: Some(String::from("finance")),
+            rare: None,
+            mining_power: Some(10),
+            mining_efficiency: Some(20),
+        }
+    }
+
+    fn company_types() -> Vec<CompanyType> {
+        vec![
+            CompanyType {
+                company_type: String::from("tech"),
+                weight: 3,
+                mining_power: StatRange { min: 5, max: 15 },
+                mining_efficiency: StatRange { min: 10, max: 30 },
+            },
+            CompanyType {
+                company_type: String::from("energy"),
+                weight: 1,
+                mining_power: StatRange { min: 40, max: 60 },
+                mining_efficiency: StatRange { min: 1, max: 5 },
+            },
+        ]
+    }
+
+    fn legendary_contract_id() -> ValidAccountId {
+        ValidAccountId::try_from("legendary.scity.testnet").unwrap()
+    }
+
+    fn legendary_box() -> BoxType {
+        BoxType {
+            box_type: String::from("legendary"),
+            box_contract_id: Some(legendary_contract_id()),
+            loot_table: LootTable {
+                company_types: vec![LootEntry {
+                    company_type: String::from("energy"),
+                    weight: 1,
+                }],
+                level: LevelRange { min: 3, max: 5 },
+                rarity_table: vec![RarityTier {
+                    rare: String::from("SSR"),
+                    weight: 1,
+                    multiplier: 2 * MULTIPLIER_BASE,
+                    supply: None,
+                }],
+            },
+        }
+    }
+
+    fn setup_box_types() -> (VMContextBuilder, Contract) {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        contract.set_company_types(company_types());
+        contract.set_box_type(legendary_box());
+        (context, contract)
+    }
+
+    const UPGRADE_COST: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn upgrade_config(currency: UpgradeCurrency) -> UpgradeConfig {
+        UpgradeConfig {
+            currency,
+            max_level: 3,
+            curve: vec![
+                UpgradeStep {
+                    cost: U128(UPGRADE_COST),
+                    multiplier: 15_000,
+                },
+                UpgradeStep {
+                    cost: U128(2 * UPGRADE_COST),
+                    multiplier: 20_000,
+                },
+            ],
+        }
+    }
+
+    /// Opens a default company for `accounts(1)` and returns its token id.
+    fn setup_upgrades(currency: UpgradeCurrency) -> (VMContextBuilder, Contract, TokenId) {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        contract.set_upgrade_config(upgrade_config(currency));
+        open_boxes(&mut context, &mut contract, 1);
+        let token_id = hex::encode(env::sha256(b"Company#0"));
+        (context, contract, token_id)
+    }
+
+    fn upgrade(context: &mut VMContextBuilder, contract: &mut Contract, token_id: &str) {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(2 * UPGRADE_COST + MINT_DEPOSIT)
+            .build());
+        contract.upgrade_company(token_id.to_string());
+    }
+
+    fn upgrade_with_token(contract: &mut Contract, token_id: &str, amount: Balance) -> Balance {
+        let msg = format!(r#"{{"upgrade_company": {{"token_id": "{}"}}}}"#, token_id);
+        match contract.ft_on_transfer(accounts(1), U128(amount), msg) {
+            PromiseOrValue::Value(unused) => unused.0,
+            PromiseOrValue::Promise(_) => panic!("Expected a value"),
+        }
+    }
+
+    fn fusion_recipe() -> FusionRecipe {
+        FusionRecipe {
+            name: String::from("triple"),
+            input_count: 3,
+            input_level: 1,
+            input_rare: None,
+            output_level: 2,
+            output_rare: Some(String::from("R")),
+            multiplier: 12_000,
+        }
+    }
+
+    /// Opens 3 default companies for `accounts(1)` and returns their token ids.
+    fn setup_fusion() -> (VMContextBuilder, Contract, Vec<TokenId>) {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        contract.set_fusion_recipes(vec![fusion_recipe()]);
+        open_boxes(&mut context, &mut contract, 3);
+        let token_ids = (0..3)
+            .map(|i| hex::encode(env::sha256(format!("Company#{}", i).as_bytes())))
+            .collect();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        (context, contract, token_ids)
+    }
+
+    fn open_boxes(context: &mut VMContextBuilder, contract: &mut Contract, amount: u128) {
+        testing_env!(context
+            .predecessor_account_id(box_contract_id())
+            .attached_deposit(0)
+            .build());
+        match contract.ft_on_transfer(accounts(1), U128(amount), String::new()) {
+            PromiseOrValue::Value(unused) => assert_eq!(unused.0, 0),
+            PromiseOrValue::Promise(_) => panic!("Expected a value"),
+        }
+    }
+
+    #[test]
+    fn test_open_box() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        open_boxes(&mut context, &mut contract, 2);
+        assert_eq!(contract.get_companies_by_owner(accounts(1).into()).len(), 2);
+        assert_eq!(contract.total, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only SBOX boxes can be opened")]
+    fn test_open_box_other_token() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.ft_on_transfer(accounts(1), U128(1), String::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "Can open between 1 and 10 boxes at once")]
+    fn test_open_too_many_boxes() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        open_boxes(&mut context, &mut contract, 11);
+    }
+
+    #[test]
+    fn test_nft_mint_minter() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        contract.grant_role(Role::Minter, accounts(1));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINT_DEPOSIT)
+            .build());
+        contract.nft_mint(
+            Some("bonus".to_string()),
+            company_metadata("bonus"),
+            Some(accounts(2)),
+        );
+        assert_eq!(
+            contract.nft_token("bonus".to_string()).unwrap().owner_id,
+            accounts(2).to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the minter role")]
+    fn test_nft_mint_without_role() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINT_DEPOSIT)
+            .build());
+        contract.nft_mint(None, company_metadata("free company"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the minter role")]
+    fn test_previous_owner_loses_privileges() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        contract.propose_owner(accounts(1));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.accept_ownership();
+        assert_eq!(contract.get_owner(), accounts(1).to_string());
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(MINT_DEPOSIT)
+            .build());
+        contract.nft_mint(None, company_metadata("free company"), None);
+    }
+
+    fn setup_paused(feature: Feature) -> (VMContextBuilder, Contract) {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        contract.grant_role(Role::Pauser, accounts(3));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.pause(Some(feature));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINT_DEPOSIT)
+            .build());
+        (context, contract)
+    }
+
+    #[test]
+    #[should_panic(expected = "The box_opening feature is paused")]
+    fn test_open_box_paused() {
+        let (mut context, mut contract) = setup_paused(Feature::BoxOpening);
+        open_boxes(&mut context, &mut contract, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The minting feature is paused")]
+    fn test_open_box_minting_paused() {
+        let (mut context, mut contract) = setup_paused(Feature::Minting);
+        open_boxes(&mut context, &mut contract, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The transfers feature is paused")]
+    fn test_nft_transfer_paused() {
+        let (mut context, mut contract) = setup_paused(Feature::Transfers);
+        open_boxes(&mut context, &mut contract, 1);
+        let token_id = hex::encode(env::sha256(b"Company#0"));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(accounts(2), token_id, None, None);
+    }
+
+    #[test]
+    fn test_update_config() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        contract.update_config(Some(accounts(4)), None, None);
+        let updated = contract.get_config();
+        assert_eq!(updated.box_contract_id, accounts(4));
+        assert_eq!(updated.land_contract_id, config().land_contract_id);
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"scity_access","version":"1.0.0","event":"config_updated","data":[{"field":"box_contract_id","old":"box.scity.testnet","new":"eugene","sender_id":"alice"}]}"#
+            ]
+        );
+
+        // Boxes are only accepted from the new box contract.
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        contract.ft_on_transfer(accounts(1), U128(1), String::new());
+        assert_eq!(contract.get_companies_by_owner(accounts(1).into()).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the admin role")]
+    fn test_update_config_without_role() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.update_config(Some(accounts(1)), None, None);
+    }
+
+    #[test]
+    fn test_open_box_rolls_catalog() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        contract.set_company_types(company_types());
+        assert_eq!(contract.get_company_types(), company_types());
+
+        testing_env!(context.random_seed(vec![7; 32]).build());
+        open_boxes(&mut context, &mut contract, 10);
+        let companies = contract.get_companies_by_owner(accounts(1).into());
+        assert_eq!(companies.len(), 10);
+        for company in &companies {
+            let company_type = company_types()
+                .into_iter()
+                .find(|t| Some(&t.company_type) == company.company_type.as_ref())
+                .expect("Company type is not in the catalog");
+            let power = company.mining_power.unwrap();
+            let efficiency = company.mining_efficiency.unwrap();
+            assert!(company_type.mining_power.min <= power);
+            assert!(power <= company_type.mining_power.max);
+            assert!(company_type.mining_efficiency.min <= efficiency);
+            assert!(efficiency <= company_type.mining_efficiency.max);
+        }
+
+        let minted = contract.get_minted_by_type();
+        assert_eq!(minted.values().sum::<u64>(), 10);
+        for (company_type, count) in minted {
+            let opened = companies
+                .iter()
+                .filter(|c| c.company_type.as_ref() == Some(&company_type))
+                .count();
+            assert_eq!(count, opened as u64);
+        }
+    }
+
+    #[test]
+    fn test_open_box_default_catalog() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        open_boxes(&mut context, &mut contract, 1);
+        let company = &contract.get_companies_by_owner(accounts(1).into())[0];
+        assert_eq!(company.company_type, Some(String::from("finance")));
+        assert_eq!(company.mining_power, Some(10));
+        assert_eq!(company.mining_efficiency, Some(20));
+        assert_eq!(contract.get_minted_by_type()["finance"], 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate company type tech")]
+    fn test_set_company_types_duplicate() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        let mut types = company_types();
+        types[1].company_type = String::from("tech");
+        contract.set_company_types(types);
+    }
+
+    #[test]
+    #[should_panic(expected = "Company catalog must have a positive weight")]
+    fn test_set_company_types_empty() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        contract.set_company_types(vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the admin role")]
+    fn test_set_company_types_without_role() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.set_company_types(company_types());
+    }
+
+    #[test]
+    fn test_open_box_tier() {
+        let (mut context, mut contract) = setup_box_types();
+        assert_eq!(contract.get_box_types().len(), 2);
+        assert_eq!(
+            contract.get_box_type(String::from("legendary")),
+            Some(legendary_box())
+        );
+
+        testing_env!(context
+            .predecessor_account_id(legendary_contract_id())
+            .random_seed(vec![3; 32])
+            .build());
+        contract.ft_on_transfer(accounts(1), U128(5), String::new());
+        let companies = contract.get_companies_by_owner(accounts(1).into());
+        assert_eq!(companies.len(), 5);
+        for company in companies {
+            assert_eq!(company.company_type, Some(String::from("energy")));
+            assert_eq!(company.rare, Some(String::from("SSR")));
+            let level = company.level.unwrap();
+            assert!((3..=5).contains(&level));
+            let power = company.mining_power.unwrap();
+            assert!((80..=120).contains(&power));
+            let efficiency = company.mining_efficiency.unwrap();
+            assert!((2..=10).contains(&efficiency));
+        }
+        let token_ids: Vec<String> = (0..5)
+            .map(|i| {
+                format!(
+                    r#""{}""#,
+                    hex::encode(env::sha256(format!("Company#{}", i).as_bytes()))
+                )
+            })
+            .collect();
+        assert_eq!(
+            get_logs(),
+            vec![
+                format!(
+                    r#"EVENT_JSON:{{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[{{"owner_id":"bob","token_ids":[{}],"memo":"open_box"}}]}}"#,
+                    token_ids.join(",")
+                ),
+                "Opened 5 legendary boxes for @bob".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_open_box_common_tier() {
+        let (mut context, mut contract) = setup_box_types();
+        open_boxes(&mut context, &mut contract, 1);
+        let company = &contract.get_companies_by_owner(accounts(1).into())[0];
+        assert_eq!(company.level, Some(1));
+        assert_eq!(company.rare, Some(String::from("C")));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only SBOX boxes can be opened")]
+    fn test_open_removed_box_type() {
+        let (mut context, mut contract) = setup_box_types();
+        contract.remove_box_type(String::from("legendary"));
+        testing_env!(context
+            .predecessor_account_id(legendary_contract_id())
+            .build());
+        contract.ft_on_transfer(accounts(1), U128(1), String::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "Company type retail is not in the catalog")]
+    fn test_set_box_type_unknown_company_type() {
+        let (_, mut contract) = setup_box_types();
+        let mut box_type = legendary_box();
+        box_type.loot_table.company_types[0].company_type = String::from("retail");
+        contract.set_box_type(box_type);
+    }
+
+    #[test]
+    #[should_panic(expected = "Loot rarity tiers cannot limit supply")]
+    fn test_set_box_type_rarity_supply() {
+        let (_, mut contract) = setup_box_types();
+        let mut box_type = legendary_box();
+        box_type.loot_table.rarity_table[0].supply = Some(10);
+        contract.set_box_type(box_type);
+    }
+
+    #[test]
+    #[should_panic(expected = "Box contract @box.scity.testnet already backs another box type")]
+    fn test_set_box_type_contract_taken() {
+        let (_, mut contract) = setup_box_types();
+        let mut box_type = legendary_box();
+        box_type.box_type = String::from("rare");
+        box_type.box_contract_id = None;
+        contract.set_box_type(box_type);
+    }
+
+    #[test]
+    #[should_panic(expected = "Company type energy is not in the catalog")]
+    fn test_set_company_types_used_by_box_type() {
+        let (_, mut contract) = setup_box_types();
+        contract.set_company_types(company_types()[..1].to_vec());
+    }
+
+    #[test]
+    fn test_upgrade_company() {
+        let (mut context, mut contract, token_id) = setup_upgrades(UpgradeCurrency::Near);
+        assert_eq!(
+            contract.get_upgrade_cost(token_id.clone()),
+            Some(U128(UPGRADE_COST))
+        );
+        upgrade(&mut context, &mut contract, &token_id);
+        let company = &contract.get_companies_by_owner(accounts(1).into())[0];
+        assert_eq!(company.level, Some(2));
+        assert_eq!(company.mining_power, Some(15));
+        assert_eq!(company.mining_efficiency, Some(30));
+        assert_eq!(
+            contract.get_upgrade_cost(token_id.clone()),
+            Some(U128(2 * UPGRADE_COST))
+        );
+
+        upgrade(&mut context, &mut contract, &token_id);
+        let company = &contract.get_companies_by_owner(accounts(1).into())[0];
+        assert_eq!(company.level, Some(3));
+        assert_eq!(company.mining_power, Some(30));
+        assert_eq!(company.mining_efficiency, Some(60));
+        assert_eq!(contract.get_upgrade_cost(token_id.clone()), None);
+
+        let history = contract.get_upgrade_history(token_id);
+        assert_eq!(history.len(), 2);
+        assert_eq!((history[0].from_level, history[0].to_level), (1, 2));
+        assert_eq!(history[1].cost, U128(2 * UPGRADE_COST));
+        assert_eq!(history[1].currency, UpgradeCurrency::Near);
+    }
+
+    #[test]
+    fn test_upgrade_company_stats_capped() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        let huge = StatRange {
+            min: u64::MAX / 2,
+            max: u64::MAX / 2,
+        };
+        contract.set_company_types(vec![CompanyType {
+            company_type: String::from("finance"),
+            weight: 1,
+            mining_power: huge.clone(),
+            mining_efficiency: huge,
+        }]);
+        contract.set_upgrade_config(upgrade_config(UpgradeCurrency::Near));
+        open_boxes(&mut context, &mut contract, 1);
+
+        let token_id = hex::encode(env::sha256(b"Company#0"));
+        upgrade(&mut context, &mut contract, &token_id);
+        upgrade(&mut context, &mut contract, &token_id);
+        let company = &contract.get_companies_by_owner(accounts(1).into())[0];
+        assert_eq!(company.mining_power, Some(u64::MAX));
+        assert_eq!(company.mining_efficiency, Some(u64::MAX));
+    }
+
+    #[test]
+    fn test_upgrade_proceeds() {
+        let (mut context, mut contract, token_id) = setup_upgrades(UpgradeCurrency::Near);
+        upgrade(&mut context, &mut contract, &token_id);
+        upgrade(&mut context, &mut contract, &token_id);
+        assert_eq!(contract.get_proceeds().available.0, 3 * UPGRADE_COST);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0)
+            .build());
+        contract.set_treasury(TreasuryConfig {
+            team_account_id: accounts(4).into(),
+            dao_account_id: accounts(5).into(),
+            team_share: 10_000,
+            dao_share: 0,
+            burn_share: 0,
+        });
+        contract.withdraw_proceeds();
+        let proceeds = contract.get_proceeds();
+        assert_eq!(proceeds.withdrawn.0, 3 * UPGRADE_COST);
+        assert_eq!(proceeds.available.0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Company is already at the max level 3")]
+    fn test_upgrade_company_max_level() {
+        let (mut context, mut contract, token_id) = setup_upgrades(UpgradeCurrency::Near);
+        for _ in 0..3 {
+            upgrade(&mut context, &mut contract, &token_id);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can upgrade this company")]
+    fn test_upgrade_company_not_owner() {
+        let (mut context, mut contract, token_id) = setup_upgrades(UpgradeCurrency::Near);
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(UPGRADE_COST + MINT_DEPOSIT)
+            .build());
+        contract.upgrade_company(token_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Must attach")]
+    fn test_upgrade_company_not_enough_deposit() {
+        let (mut context, mut contract, token_id) = setup_upgrades(UpgradeCurrency::Near);
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(UPGRADE_COST - 1)
+            .build());
+        contract.upgrade_company(token_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Company upgrades are not configured")]
+    fn test_upgrade_company_not_configured() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        open_boxes(&mut context, &mut contract, 1);
+        upgrade(
+            &mut context,
+            &mut contract,
+            &hex::encode(env::sha256(b"Company#0")),
+        );
+    }
+
+    #[test]
+    fn test_upgrade_company_with_token() {
+        let (mut context, mut contract, token_id) =
+            setup_upgrades(UpgradeCurrency::Token(accounts(3)));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINT_DEPOSIT)
+            .build());
+        let deposit = contract.deposit_upgrade_storage().0;
+        assert!(deposit < MINT_DEPOSIT);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(0)
+            .build());
+        let storage_usage = env::storage_usage();
+        assert_eq!(
+            upgrade_with_token(&mut contract, &token_id, UPGRADE_COST + 5),
+            5
+        );
+        // The storage of the upgrade history is paid from the sender's deposit.
+        let storage_cost =
+            Balance::from(env::storage_usage() - storage_usage) * env::storage_byte_cost();
+        assert_eq!(
+            contract.get_upgrade_storage_deposit(accounts(1)).0,
+            deposit - storage_cost
+        );
+        let history = contract.get_upgrade_history(token_id);
+        assert_eq!(history[0].currency, UpgradeCurrency::Token(accounts(3)));
+        // Token payments are burned, not collected.
+        assert_eq!(contract.get_proceeds().collected.0, 0);
+        assert_eq!(
+            contract.get_companies_by_owner(accounts(1).into())[0].level,
+            Some(2)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The upgrades feature is paused")]
+    fn test_upgrade_company_with_token_paused() {
+        let (mut context, mut contract, token_id) =
+            setup_upgrades(UpgradeCurrency::Token(accounts(3)));
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.pause(Some(Feature::Upgrades));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        let msg = format!(r#"{{"upgrade_company": {{"token_id": "{}"}}}}"#, token_id);
+        contract.ft_on_transfer(accounts(1), U128(UPGRADE_COST), msg);
+    }
+
+    #[test]
+    #[should_panic(expected = "with deposit_upgrade_storage to cover storage")]
+    fn test_upgrade_company_with_token_without_storage_deposit() {
+        let (mut context, mut contract, token_id) =
+            setup_upgrades(UpgradeCurrency::Token(accounts(3)));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        upgrade_with_token(&mut contract, &token_id, UPGRADE_COST);
+    }
+
+    #[test]
+    fn test_withdraw_upgrade_storage() {
+        let (mut context, mut contract, _) = setup_upgrades(UpgradeCurrency::Token(accounts(3)));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINT_DEPOSIT)
+            .build());
+        let deposit = contract.deposit_upgrade_storage().0;
+        testing_env!(context.attached_deposit(1).build());
+        assert_eq!(contract.withdraw_upgrade_storage(None).0, deposit);
+        assert_eq!(contract.get_upgrade_storage_deposit(accounts(1)).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The upgrades feature is paused")]
+    fn test_upgrade_company_paused() {
+        let (mut context, mut contract, token_id) = setup_upgrades(UpgradeCurrency::Near);
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.pause(None);
+        upgrade(&mut context, &mut contract, &token_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Upgrades are paid in another currency")]
+    fn test_upgrade_company_wrong_currency() {
+        let (mut context, mut contract, token_id) =
+            setup_upgrades(UpgradeCurrency::Token(accounts(3)));
+        upgrade(&mut context, &mut contract, &token_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Must send 1000000000000000000000000 tokens to cover the price")]
+    fn test_upgrade_company_not_enough_tokens() {
+        let (mut context, mut contract, token_id) =
+            setup_upgrades(UpgradeCurrency::Token(accounts(3)));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        let msg = format!(r#"{{"upgrade_company": {{"token_id": "{}"}}}}"#, token_id);
+        contract.ft_on_transfer(accounts(1), U128(1), msg);
+    }
+
+    #[test]
+    fn test_fuse_companies() {
+        let (_, mut contract, token_ids) = setup_fusion();
+        assert_eq!(contract.get_fusion_recipes(), vec![fusion_recipe()]);
+        let token_id = contract.fuse_companies(token_ids.clone());
+        assert_eq!(token_id, hex::encode(env::sha256(b"Company#3")));
+
+        let companies = contract.get_companies_by_owner(accounts(1).into());
+        assert_eq!(companies.len(), 1);
+        assert_eq!(companies[0].company_type, Some(String::from("finance")));
+        assert_eq!(companies[0].level, Some(2));
+        assert_eq!(companies[0].rare, Some(String::from("R")));
+        assert_eq!(companies[0].mining_power, Some(36));
+        assert_eq!(companies[0].mining_efficiency, Some(72));
+        assert!(contract.nft_token(token_ids[0].clone()).is_none());
+
+        let logs = get_logs();
+        assert_eq!(
+            logs[0],
+            format!(
+                r#"EVENT_JSON:{{"standard":"nep171","version":"1.0.0","event":"nft_burn","data":[{{"owner_id":"bob","token_ids":["{}","{}","{}"],"memo":"triple"}}]}}"#,
+                token_ids[0], token_ids[1], token_ids[2]
+            )
+        );
+        assert_eq!(
+            logs[1],
+            format!(
+                r#"EVENT_JSON:{{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[{{"owner_id":"bob","token_ids":["{}"],"memo":"triple"}}]}}"#,
+                token_id
+            )
+        );
+    }
+
+    #[test]
+    fn test_fuse_companies_refunds_approvals() {
+        let (mut context, mut contract, token_ids) = setup_fusion();
+        testing_env!(context.attached_deposit(10u128.pow(22)).build());
+        contract.nft_approve(token_ids[1].clone(), accounts(3), None);
+        testing_env!(context.attached_deposit(1).build());
+        contract.fuse_companies(token_ids);
+
+        let refund = Balance::from(bytes_for_approved_account_id(&accounts(3).into()))
+            * env::storage_byte_cost();
+        // Receipt fields are private, match on its debug output.
+        let receipt = format!("{:?}", get_created_receipts().last().unwrap());
+        assert!(receipt.contains(r#"receiver_id: "bob""#));
+        assert!(receipt.contains(&format!(
+            "Transfer(TransferAction {{ deposit: {} }})",
+            refund
+        )));
+    }
+
+    #[test]
+    #[should_panic(expected = "No fusion recipe matches these companies")]
+    fn test_fuse_companies_no_recipe() {
+        let (_, mut contract, token_ids) = setup_fusion();
+        contract.fuse_companies(token_ids[..2].to_vec());
+    }
+
+    #[test]
+    #[should_panic(expected = "The fusion feature is paused")]
+    fn test_fuse_companies_paused() {
+        let (mut context, mut contract, token_ids) = setup_fusion();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.pause(None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.fuse_companies(token_ids);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only companies of the same type can be fused")]
+    fn test_fuse_companies_mixed_types() {
+        let (_, mut contract, token_ids) = setup_fusion();
+        let mut metadata = contract
+            .tokens
+            .token_metadata_by_id
+            .get(&token_ids[2])
+            .unwrap();
+        metadata.company_type = Some(String::from("tech"));
+        contract
+            .tokens
+            .token_metadata_by_id
+            .insert(&token_ids[2], &metadata);
+        contract.fuse_companies(token_ids);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot fuse company")]
+    fn test_fuse_companies_duplicate() {
+        let (_, mut contract, token_ids) = setup_fusion();
+        contract.fuse_companies(vec![
+            token_ids[0].clone(),
+            token_ids[1].clone(),
+            token_ids[0].clone(),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can fuse company")]
+    fn test_fuse_companies_not_owner() {
+        let (mut context, mut contract, token_ids) = setup_fusion();
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        contract.fuse_companies(token_ids);
+    }
+
+    #[test]
+    #[should_panic(expected = "Recipes must fuse at least 2 companies")]
+    fn test_set_fusion_recipes_invalid() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        let mut recipe = fusion_recipe();
+        recipe.input_count = 1;
+        contract.set_fusion_recipes(vec![recipe]);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::*;
+
+/// Inclusive range of levels a company opens at.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LevelRange {
+    pub min: u8,
+    pub max: u8,
+}
+
+/// A company type a box can open into, weighted against the others in its loot table.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LootEntry {
+    /// Name of a type in the company catalog.
+    pub company_type: String,
+    pub weight: u32,
+}
+
+/// What the boxes of a tier open into.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LootTable {
+    /// Company types to roll from, the whole catalog with its own weights if empty.
+    pub company_types: Vec<LootEntry>,
+    pub level: LevelRange,
+    pub rarity_table: Vec<RarityTier>,
+}
+
+/// A box tier, e.g. common, rare or legendary. Each tier is its own SBOX contract, so it is
+/// sold at its own price.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BoxType {
+    pub box_type: String,
+    /// Contract of the tier's boxes, the configured box contract if `None`.
+    pub box_contract_id: Option<ValidAccountId>,
+    pub loot_table: LootTable,
+}
+
+/// Tiers used until the owner configures others: common boxes from the configured box
+/// contract, opening into level 1 companies of the whole catalog.
+pub(crate) fn default_box_types() -> Vec<BoxType> {
+    vec![BoxType {
+        box_type: String::from("common"),
+        box_contract_id: None,
+        loot_table: LootTable {
+            company_types: Vec::new(),
+            level: LevelRange { min: 1, max: 1 },
+            rarity_table: vec![RarityTier {
+                rare: String::from("C"),
+                weight: 1,
+                multiplier: MULTIPLIER_BASE,
+                supply: None,
+            }],
+        },
+    }]
+}
+
+impl LevelRange {
+    fn roll(&self, value: u64) -> u8 {
+        let span = u64::from(self.max - self.min) + 1;
+        self.min + (value % span) as u8
+    }
+}
+
+impl LootTable {
+    pub(crate) fn assert_valid(&self, company_types: &[CompanyType]) {
+        assert!(
+            self.level.min >= 1 && self.level.min <= self.level.max,
+            "Level range must start at 1 or above and min must not exceed max"
+        );
+        if !self.company_types.is_empty() {
+            assert!(
+                self.company_types.iter().any(|entry| entry.weight > 0),
+                "Loot table must have a positive weight"
+            );
+        }
+        for entry in &self.company_types {
+            assert!(
+                company_types
+                    .iter()
+                    .any(|company_type| company_type.company_type == entry.company_type),
+                "Company type {} is not in the catalog",
+                entry.company_type
+            );
+        }
+        assert_valid_rarity_table(&self.rarity_table);
+        assert!(
+            self.rarity_table.iter().all(|tier| tier.supply.is_none()),
+            "Loot rarity tiers cannot limit supply"
+        );
+    }
+
+    /// Rolls the type, level, rarity and stats of `token_id` from the block random seed.
+    pub(crate) fn roll(
+        &self,
+        company_types: &[CompanyType],
+        seed: &[u8],
+        token_id: &str,
+    ) -> CompanyStats {
+        let value = random_u64(seed, token_id, "company_type");
+        let company_type = if self.company_types.is_empty() {
+            pick_weighted(company_types, |company_type| company_type.weight, value)
+        } else {
+            let entry = pick_weighted(&self.company_types, |entry| entry.weight, value);
+            company_types
+                .iter()
+                .find(|company_type| company_type.company_type == entry.company_type)
+                .unwrap_or_else(|| {
+                    env::panic(
+                        format!("Company type {} is not in the catalog", entry.company_type)
+                            .as_bytes(),
+                    )
+                })
+        };
+        let tier = pick_weighted(
+            &self.rarity_table,
+            |tier| tier.weight,
+            random_u64(seed, token_id, "rare"),
+        );
+        let scale = |stat: u64| apply_multiplier(stat, tier.multiplier);
+        CompanyStats {
+            company_type: company_type.company_type.clone(),
+            level: self.level.roll(random_u64(seed, token_id, "level")),
+            rare: tier.rare.clone(),
+            mining_power: scale(company_type.mining_power.roll(random_u64(
+                seed,
+                token_id,
+                "mining_power",
+            ))),
+            mining_efficiency: scale(company_type.mining_efficiency.roll(random_u64(
+                seed,
+                token_id,
+                "mining_efficiency",
+            ))),
+        }
+    }
+}
+
+impl Contract {
+    /// Account boxes of the given tier are sent from.
+    pub(crate) fn box_contract_id(&self, box_type: &BoxType) -> AccountId {
+        match &box_type.box_contract_id {
+            Some(box_contract_id) => box_contract_id.clone().into(),
+            None => self.config.box_contract_id.clone().into(),
+        }
+    }
+
+    pub(crate) fn internal_box_type_from(&self, box_contract_id: &str) -> Option<BoxType> {
+        self.box_types
+            .iter()
+            .find(|box_type| self.box_contract_id(box_type) == box_contract_id)
+            .cloned()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Adds a box tier, or replaces the tier of the same name.
+    pub fn set_box_type(&mut self, box_type: BoxType) {
+        self.assert_role(Role::Admin);
+        box_type.loot_table.assert_valid(&self.company_types);
+        let box_contract_id = self.box_contract_id(&box_type);
+        assert!(
+            self.box_types
+                .iter()
+                .all(|other| other.box_type == box_type.box_type
+                    || self.box_contract_id(other) != box_contract_id),
+            "Box contract @{} already backs another box type",
+            box_contract_id
+        );
+        match self
+            .box_types
+            .iter_mut()
+            .find(|other| other.box_type == box_type.box_type)
+        {
+            Some(existing) => *existing = box_type,
+            None => self.box_types.push(box_type),
+        }
+    }
+
+    /// Stops opening boxes of the given tier.
+    pub fn remove_box_type(&mut self, box_type: String) {
+        self.assert_role(Role::Admin);
+        let len = self.box_types.len();
+        self.box_types.retain(|other| other.box_type != box_type);
+        assert!(
+            self.box_types.len() < len,
+            "Box type {} does not exist",
+            box_type
+        );
+    }
+
+    pub fn get_box_types(&self) -> Vec<BoxType> {
+        self.box_types.clone()
+    }
+
+    pub fn get_box_type(&self, box_type: String) -> Option<BoxType> {
+        self.box_types
+            .iter()
+            .find(|other| other.box_type == box_type)
+            .cloned()
+    }
+}
@@ -1,38 +1,24 @@
-use crate::*;
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
-#[serde(crate = "near_sdk::serde")]
-pub struct NFTMetadata {
-    pub spec: String,              // required, essentially a version like "nft-1.0.0"
-    pub name: String,              // required, ex. "Mosaics"
-    pub symbol: String,            // required, ex. "MOSIAC"
-    pub icon: Option<String>,      // Data URL
-    pub base_uri: Option<String>, // Centralized gateway known to have reliable access to decentralized storage assets referenced by `reference` or `media` URLs
-    pub reference: Option<String>, // URL to a JSON file with more info
-    pub reference_hash: Option<Base64VecU8>, // Base64-encoded sha256 hash of JSON from reference field. Required if `reference` is included.
-}
-
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
-#[serde(crate = "near_sdk::serde")]
-pub struct TokenMetadata {
-    pub title: Option<String>,
-    pub description: Option<String>,
-    pub media: Option<String>,
-    pub media_hash: Option<Base64VecU8>,
-    pub copies: Option<u64>,
-    pub issued_at: Option<u64>,
-    pub level: Option<u8>,
-    pub company_type: Option<String>,
-    pub mining_power: Option<u64>,
-    pub mining_efficiency: Option<u64>,
-}
-
-pub trait NonFungibleTokenMetadata {
-    fn nft_metadata(&self) -> NFTMetadata;
-}
-
-#[near_bindgen]
-impl NonFungibleTokenMetadata for Contract {
-    fn nft_metadata(&self) -> NFTMetadata {
-        self.metadata.get().unwrap()
-    }
-}
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub media: Option<String>,
+    pub media_hash: Option<Base64VecU8>,
+    pub copies: Option<u64>,
+    pub issued_at: Option<u64>,
+    pub level: Option<u8>,
+    pub company_type: Option<String>,
+    pub rare: Option<String>,
+    pub mining_power: Option<u64>,
+    pub mining_efficiency: Option<u64>,
+}
+
+#[near_bindgen]
+impl NonFungibleTokenMetadata for Contract {
+    fn nft_metadata(&self) -> NFTMetadata {
+        self.metadata.get().unwrap()
+    }
+}
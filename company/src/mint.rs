@@ -1,44 +1,78 @@
-use crate::*;
-
-#[near_bindgen]
-impl Contract {
-    /// only the contract owner can mint NFTs
-    #[payable]
-    pub fn nft_mint(
-        &mut self,
-        token_id: Option<TokenId>,
-        metadata: TokenMetadata,
-        receiver_id: Option<ValidAccountId>,
-    ) {
-        // self.assert_owner();
-
-        let mut final_token_id = format!("{}", self.token_metadata_by_id.len() + 1);
-        if let Some(token_id) = token_id {
-            final_token_id = token_id
-        }
-
-        let initial_storage_usage = env::storage_usage();
-        let mut owner_id = env::predecessor_account_id();
-        if let Some(receiver_id) = receiver_id {
-            owner_id = receiver_id.into();
-        }
-
-        let token = Token {
-            owner_id,
-            approved_account_ids: Default::default(),
-            next_approval_id: 0,
-        };
-        assert!(
-            self.tokens_by_id.insert(&final_token_id, &token).is_none(),
-            "Token already exists"
-        );
-        self.token_metadata_by_id.insert(&final_token_id, &metadata);
-        self.internal_add_token_to_owner(&token.owner_id, &final_token_id);
-
-        let new_token_size_in_bytes = env::storage_usage() - initial_storage_usage;
-        let required_storage_in_bytes =
-            self.extra_storage_in_bytes_per_token + new_token_size_in_bytes;
-
-        refund_deposit(required_storage_in_bytes);
-    }
-}
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Only the owner and minters can mint NFTs.
+    #[payable]
+    pub fn nft_mint(
+        &mut self,
+        token_id: Option<TokenId>,
+        metadata: TokenMetadata,
+        receiver_id: Option<ValidAccountId>,
+    ) {
+        self.assert_role(Role::Minter);
+
+        let required_storage_in_bytes = self.internal_nft_mint(token_id, metadata, receiver_id);
+
+        refund_deposit(required_storage_in_bytes);
+    }
+}
+
+impl Contract {
+    /// Mints a token without charging for it and returns the storage it used.
+    pub(crate) fn internal_nft_mint(
+        &mut self,
+        token_id: Option<TokenId>,
+        metadata: TokenMetadata,
+        receiver_id: Option<ValidAccountId>,
+    ) -> StorageUsage {
+        self.pausable.assert_not_paused(Feature::Minting);
+        let mut final_token_id = format!("{}", self.tokens.token_metadata_by_id.len() + 1);
+        if let Some(token_id) = token_id {
+            final_token_id = token_id
+        }
+
+        let mut owner_id = env::predecessor_account_id();
+        if let Some(receiver_id) = receiver_id {
+            owner_id = receiver_id.into();
+        }
+
+        self.tokens
+            .internal_mint(final_token_id, owner_id, &metadata, HashMap::new())
+    }
+
+    /// Mints the next company to `receiver_id`, rolled from the loot table of the opened box
+    /// and paid for by the contract. Returns the new token id.
+    pub(crate) fn internal_mint_company(
+        &mut self,
+        receiver_id: ValidAccountId,
+        loot_table: &LootTable,
+        seed: &[u8],
+    ) -> TokenId {
+        let new_name = String::from("Company#") + &self.total.to_string();
+        let token_id = hex::encode(env::sha256(new_name.as_bytes()));
+        let stats = loot_table.roll(&self.company_types, seed, &token_id);
+        *self
+            .minted_by_type
+            .entry(stats.company_type.clone())
+            .or_insert(0) += 1;
+
+        let token: TokenMetadata = TokenMetadata {
+            title: Some(new_name.clone()),
+            description: Some(new_name),
+            media: None,
+            media_hash: None,
+            copies: Some(1),
+            issued_at: Some(env::block_timestamp()),
+            level: Some(stats.level),
+            company_type: Some(stats.company_type),
+            rare: Some(stats.rare),
+            mining_power: Some(stats.mining_power),
+            mining_efficiency: Some(stats.mining_efficiency),
+        };
+
+        self.total += 1;
+        self.internal_nft_mint(Some(token_id.clone()), token, Some(receiver_id));
+        token_id
+    }
+}
@@ -1,20 +0,0 @@
-use crate::*;
-
-pub type TokenId = String;
-pub type Payout = HashMap<AccountId, U128>;
-
-#[derive(BorshDeserialize, BorshSerialize)]
-pub struct Token {
-    pub owner_id: AccountId,
-    pub approved_account_ids: HashMap<AccountId, U64>,
-    pub next_approval_id: u64,
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(crate = "near_sdk::serde")]
-pub struct JsonToken {
-    pub token_id: TokenId,
-    pub owner_id: AccountId,
-    pub metadata: TokenMetadata,
-    pub approved_account_ids: HashMap<AccountId, U64>,
-}
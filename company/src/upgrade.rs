@@ -0,0 +1,237 @@
+use crate::*;
+
+/// What company upgrades are paid in.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum UpgradeCurrency {
+    /// Attached to `upgrade_company`.
+    Near,
+    /// Sent with `ft_transfer_call` on the given NEP-141 contract, e.g. SBOX or SCITY, which
+    /// must have an `ft_burn` entrypoint. Payments are burned.
+    Token(ValidAccountId),
+}
+
+/// Cost and stat growth of upgrading a company by one level.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UpgradeStep {
+    pub cost: U128,
+    /// Applied to both mining stats, in basis points of [`MULTIPLIER_BASE`].
+    pub multiplier: u32,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UpgradeConfig {
+    pub currency: UpgradeCurrency,
+    pub max_level: u8,
+    /// Step `i` upgrades a company from level `i + 1`. The last step repeats for higher levels.
+    pub curve: Vec<UpgradeStep>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UpgradeRecord {
+    pub from_level: u8,
+    pub to_level: u8,
+    pub cost: U128,
+    pub currency: UpgradeCurrency,
+    pub mining_power: u64,
+    pub mining_efficiency: u64,
+    pub upgraded_at: Timestamp,
+}
+
+impl UpgradeConfig {
+    fn assert_valid(&self) {
+        assert!(self.max_level > 1, "Max level must be above 1");
+        assert!(
+            !self.curve.is_empty(),
+            "Upgrade curve must have at least one step"
+        );
+        for step in &self.curve {
+            assert!(step.multiplier > 0, "Upgrade multiplier must be positive");
+        }
+    }
+
+    fn step(&self, level: u8) -> &UpgradeStep {
+        let index = usize::from(level.saturating_sub(1)).min(self.curve.len() - 1);
+        &self.curve[index]
+    }
+}
+
+impl Contract {
+    /// Raises the level of `owner_id`'s company by one and returns the cost, which the caller
+    /// collects in `currency`.
+    pub(crate) fn internal_upgrade_company(
+        &mut self,
+        owner_id: &AccountId,
+        token_id: &TokenId,
+        currency: &UpgradeCurrency,
+    ) -> Balance {
+        self.pausable.assert_not_paused(Feature::Upgrades);
+        let config = self
+            .upgrade_config
+            .clone()
+            .expect("Company upgrades are not configured");
+        assert_eq!(
+            &config.currency, currency,
+            "Upgrades are paid in another currency"
+        );
+        let token = self
+            .tokens
+            .tokens_by_id
+            .get(token_id)
+            .expect("Token not found");
+        assert_eq!(
+            &token.owner_id, owner_id,
+            "Only the owner can upgrade this company"
+        );
+
+        let mut metadata = self.tokens.token_metadata_by_id.get(token_id).unwrap();
+        let from_level = metadata.level.unwrap_or(1);
+        assert!(
+            from_level < config.max_level,
+            "Company is already at the max level {}",
+            config.max_level
+        );
+        let step = config.step(from_level);
+        let scale = |stat: Option<u64>| apply_multiplier(stat.unwrap_or(0), step.multiplier);
+        let record = UpgradeRecord {
+            from_level,
+            to_level: from_level + 1,
+            cost: step.cost,
+            currency: config.currency.clone(),
+            mining_power: scale(metadata.mining_power),
+            mining_efficiency: scale(metadata.mining_efficiency),
+            upgraded_at: env::block_timestamp(),
+        };
+        metadata.level = Some(record.to_level);
+        metadata.mining_power = Some(record.mining_power);
+        metadata.mining_efficiency = Some(record.mining_efficiency);
+        self.tokens.token_metadata_by_id.insert(token_id, &metadata);
+
+        let mut history = self.upgrade_history.get(token_id).unwrap_or_default();
+        history.push(record.clone());
+        self.upgrade_history.insert(token_id, &history);
+
+        env::log(
+            format!(
+                "Upgraded company {} of @{} from level {} to {} for {}",
+                token_id, owner_id, record.from_level, record.to_level, record.cost.0
+            )
+            .as_bytes(),
+        );
+        record.cost.0
+    }
+
+    /// Takes the cost of `storage_used` bytes from the account's storage deposit.
+    pub(crate) fn internal_charge_upgrade_storage(
+        &mut self,
+        account_id: &AccountId,
+        storage_used: u64,
+    ) {
+        let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
+        let balance = self.upgrade_storage_deposits.get(account_id).unwrap_or(0);
+        assert!(
+            required_cost <= balance,
+            "Must deposit {} yoctoNEAR with deposit_upgrade_storage to cover storage",
+            required_cost
+        );
+        self.upgrade_storage_deposits
+            .insert(account_id, &(balance - required_cost));
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Upgrades the caller's company by one level, paid in NEAR with storage on top. The price
+    /// is collected as proceeds for the treasury. Upgrades paid in a token are sent with
+    /// `ft_transfer_call` on the token contract and the message
+    /// `{"upgrade_company": {"token_id": [token_id]}}` instead, with their storage paid from
+    /// `deposit_upgrade_storage`.
+    #[payable]
+    pub fn upgrade_company(&mut self, token_id: TokenId) {
+        let initial_storage_usage = env::storage_usage();
+        let cost = self.internal_upgrade_company(
+            &env::predecessor_account_id(),
+            &token_id,
+            &UpgradeCurrency::Near,
+        );
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+        refund_deposit_with_price(storage_used, cost);
+        self.proceeds.collected += cost;
+    }
+
+    /// Deposits NEAR for the storage of the caller's upgrades paid in tokens and returns the
+    /// caller's balance. The first deposit also pays for the balance's own storage.
+    #[payable]
+    pub fn deposit_upgrade_storage(&mut self) -> U128 {
+        let initial_storage_usage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        let balance = self.upgrade_storage_deposits.get(&account_id).unwrap_or(0);
+        self.upgrade_storage_deposits.insert(&account_id, &balance);
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+        let entry_cost = env::storage_byte_cost() * Balance::from(storage_used);
+        let deposit = env::attached_deposit();
+        assert!(
+            deposit > entry_cost,
+            "Must attach more than {} yoctoNEAR to cover the deposit's storage",
+            entry_cost
+        );
+        let balance = balance + deposit - entry_cost;
+        self.upgrade_storage_deposits.insert(&account_id, &balance);
+        U128(balance)
+    }
+
+    /// Withdraws `amount` of the caller's unused storage deposit, or all of it if `None`.
+    #[payable]
+    pub fn withdraw_upgrade_storage(&mut self, amount: Option<U128>) -> U128 {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let balance = self.upgrade_storage_deposits.get(&account_id).unwrap_or(0);
+        let amount = amount.map_or(balance, |amount| amount.0);
+        assert!(
+            amount > 0 && amount <= balance,
+            "Can withdraw between 1 and {} yoctoNEAR",
+            balance
+        );
+        self.upgrade_storage_deposits
+            .insert(&account_id, &(balance - amount));
+        Promise::new(account_id).transfer(amount);
+        U128(amount)
+    }
+
+    pub fn get_upgrade_storage_deposit(&self, account_id: ValidAccountId) -> U128 {
+        U128(
+            self.upgrade_storage_deposits
+                .get(account_id.as_ref())
+                .unwrap_or(0),
+        )
+    }
+
+    pub fn set_upgrade_config(&mut self, config: UpgradeConfig) {
+        self.assert_role(Role::Admin);
+        config.assert_valid();
+        self.upgrade_config = Some(config);
+    }
+
+    pub fn get_upgrade_config(&self) -> Option<UpgradeConfig> {
+        self.upgrade_config.clone()
+    }
+
+    /// Cost of the company's next upgrade, `None` at the max level.
+    pub fn get_upgrade_cost(&self, token_id: TokenId) -> Option<U128> {
+        let config = self.upgrade_config.as_ref()?;
+        let metadata = self.tokens.token_metadata_by_id.get(&token_id)?;
+        let level = metadata.level.unwrap_or(1);
+        if level >= config.max_level {
+            return None;
+        }
+        Some(config.step(level).cost)
+    }
+
+    pub fn get_upgrade_history(&self, token_id: TokenId) -> Vec<UpgradeRecord> {
+        self.upgrade_history.get(&token_id).unwrap_or_default()
+    }
+}
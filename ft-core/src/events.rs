@@ -0,0 +1,49 @@
+use near_sdk::serde_json::{json, Value};
+use near_sdk::{env, AccountId, Balance};
+
+/// Logs a NEP-297 event of the NEP-141 standard.
+fn emit(event: &str, mut data: Value, memo: Option<&str>) {
+    if let Some(memo) = memo {
+        data["memo"] = json!(memo);
+    }
+    let event = json!({
+        "standard": "nep141",
+        "version": "1.0.0",
+        "event": event,
+        "data": [data],
+    });
+    env::log(format!("EVENT_JSON:{}", event).as_bytes());
+}
+
+pub fn emit_ft_mint(owner_id: &AccountId, amount: Balance, memo: Option<&str>) {
+    emit(
+        "ft_mint",
+        json!({ "owner_id": owner_id, "amount": amount.to_string() }),
+        memo,
+    );
+}
+
+pub fn emit_ft_transfer(
+    old_owner_id: &AccountId,
+    new_owner_id: &AccountId,
+    amount: Balance,
+    memo: Option<&str>,
+) {
+    emit(
+        "ft_transfer",
+        json!({
+            "old_owner_id": old_owner_id,
+            "new_owner_id": new_owner_id,
+            "amount": amount.to_string(),
+        }),
+        memo,
+    );
+}
+
+pub fn emit_ft_burn(owner_id: &AccountId, amount: Balance, memo: Option<&str>) {
+    emit(
+        "ft_burn",
+        json!({ "owner_id": owner_id, "amount": amount.to_string() }),
+        memo,
+    );
+}
@@ -0,0 +1,13 @@
+//! Fungible token core shared by the Scity box and token contracts.
+//!
+//! Both contracts embed the standard `FungibleToken` and expose its transfer and resolve methods
+//! with [`impl_fungible_token_core!`], which stops transfers while they are paused and logs every
+//! mint, transfer and burn as a NEP-141 event.
+pub use crate::events::*;
+#[doc(hidden)]
+pub use near_contract_standards;
+#[doc(hidden)]
+pub use scity_access;
+
+mod events;
+mod macros;
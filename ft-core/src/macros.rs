@@ -0,0 +1,110 @@
+/// Exposes the core transfer and resolve methods of the contract's `FungibleToken` field.
+/// Transfers stop while the transfers feature of the contract's
+/// [`Pausable`](scity_access::Pausable) field is paused. Refunds of `ft_transfer_call` are never
+/// paused, they only return tokens to the sender.
+///
+/// `$on_tokens_burned` is called with the sender and amount when a refund is burned because the
+/// sender account was deleted.
+#[macro_export]
+macro_rules! impl_fungible_token_core {
+    ($contract: ident, $token: ident, $pausable: ident, $on_tokens_burned: ident) => {
+        #[near_sdk::near_bindgen]
+        impl $crate::near_contract_standards::fungible_token::core::FungibleTokenCore
+            for $contract
+        {
+            #[payable]
+            fn ft_transfer(
+                &mut self,
+                receiver_id: near_sdk::json_types::ValidAccountId,
+                amount: near_sdk::json_types::U128,
+                memo: Option<String>,
+            ) {
+                self.$pausable
+                    .assert_not_paused($crate::scity_access::Feature::Transfers);
+                $crate::emit_ft_transfer(
+                    &near_sdk::env::predecessor_account_id(),
+                    receiver_id.as_ref(),
+                    amount.0,
+                    memo.as_deref(),
+                );
+                $crate::near_contract_standards::fungible_token::core::FungibleTokenCore::ft_transfer(
+                    &mut self.$token,
+                    receiver_id,
+                    amount,
+                    memo,
+                )
+            }
+
+            #[payable]
+            fn ft_transfer_call(
+                &mut self,
+                receiver_id: near_sdk::json_types::ValidAccountId,
+                amount: near_sdk::json_types::U128,
+                memo: Option<String>,
+                msg: String,
+            ) -> near_sdk::PromiseOrValue<near_sdk::json_types::U128> {
+                self.$pausable
+                    .assert_not_paused($crate::scity_access::Feature::Transfers);
+                $crate::emit_ft_transfer(
+                    &near_sdk::env::predecessor_account_id(),
+                    receiver_id.as_ref(),
+                    amount.0,
+                    memo.as_deref(),
+                );
+                $crate::near_contract_standards::fungible_token::core::FungibleTokenCore::ft_transfer_call(
+                    &mut self.$token,
+                    receiver_id,
+                    amount,
+                    memo,
+                    msg,
+                )
+            }
+
+            fn ft_total_supply(&self) -> near_sdk::json_types::U128 {
+                $crate::near_contract_standards::fungible_token::core::FungibleTokenCore::ft_total_supply(&self.$token)
+            }
+
+            fn ft_balance_of(
+                &self,
+                account_id: near_sdk::json_types::ValidAccountId,
+            ) -> near_sdk::json_types::U128 {
+                $crate::near_contract_standards::fungible_token::core::FungibleTokenCore::ft_balance_of(&self.$token, account_id)
+            }
+        }
+
+        #[near_sdk::near_bindgen]
+        impl $crate::near_contract_standards::fungible_token::resolver::FungibleTokenResolver
+            for $contract
+        {
+            #[private]
+            fn ft_resolve_transfer(
+                &mut self,
+                sender_id: near_sdk::json_types::ValidAccountId,
+                receiver_id: near_sdk::json_types::ValidAccountId,
+                amount: near_sdk::json_types::U128,
+            ) -> near_sdk::json_types::U128 {
+                let sender_id: near_sdk::AccountId = sender_id.into();
+                let receiver: near_sdk::AccountId = receiver_id.clone().into();
+                let (used_amount, burned_amount) = self
+                    .$token
+                    .internal_ft_resolve_transfer(&sender_id, receiver_id, amount);
+                if burned_amount > 0 {
+                    $crate::emit_ft_burn(
+                        &receiver,
+                        burned_amount,
+                        Some("Sender account was deleted"),
+                    );
+                    self.$on_tokens_burned(sender_id, burned_amount);
+                } else if used_amount < amount.0 {
+                    $crate::emit_ft_transfer(
+                        &receiver,
+                        &sender_id,
+                        amount.0 - used_amount,
+                        Some("refund"),
+                    );
+                }
+                used_amount.into()
+            }
+        }
+    };
+}
@@ -1,6 +1,6 @@
-#!/bin/bash
-set -e
-
-RUSTFLAGS='-C link-arg=-s' cargo build --target wasm32-unknown-unknown --release
-mkdir -p ../../out
-cp target/wasm32-unknown-unknown/release/*.wasm ./res/main.wasm
+#!/bin/bash
+set -e
+
+RUSTFLAGS='-C link-arg=-s' cargo build -p scity-land --target wasm32-unknown-unknown --release
+mkdir -p ../../out
+cp ../target/wasm32-unknown-unknown/release/scity_land.wasm ./res/main.wasm
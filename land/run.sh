@@ -1,5 +1,5 @@
 near deploy --wasm-file res/main.wasm --account-id land.dev.scity.testnet --master-account dev.scity.testnet
 
-near call land.dev.scity.testnet new_default_meta '{"owner_id": "dev.scity.testnet"}' --account-id dev.scity.testnet
+near call land.dev.scity.testnet new_default_meta '{"owner_id": "dev.scity.testnet", "config": {"box_contract_id": "box.dev.scity.testnet", "land_contract_id": "land.dev.scity.testnet", "company_contract_id": "company.dev.scity.testnet"}}' --account-id dev.scity.testnet
 
-near call land.dev.scity.testnet open_area '{"name": "toronto", "limit": 900, "price": "100000000000000000000000", "open_time": 1645030800, "close_time": 1650128399}' --account-id dev.scity.testnet
\ No newline at end of file
+near call land.dev.scity.testnet create_area '{"name": "toronto", "limit": 900, "width": 30, "height": 30, "price": "100000000000000000000000", "open_time": 1645030800000000000, "close_time": 1650128399000000000}' --account-id dev.scity.testnet
\ No newline at end of file
@@ -0,0 +1,94 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Updates the sale settings of an existing area. Fields left out are kept, and lands sold,
+    /// minted cells and proceeds are never reset. A `max_per_account` of `0` removes the cap.
+    pub fn update_area(
+        &mut self,
+        name: String,
+        limit: Option<u64>,
+        price: Option<String>,
+        open_time: Option<Timestamp>,
+        close_time: Option<Timestamp>,
+        max_per_account: Option<u64>,
+    ) {
+        self.assert_role(Role::AreaManager);
+        let (area_hash, mut area) = self.internal_get_area(&name);
+        assert!(!area.closed, "Area is closed");
+
+        if let Some(limit) = limit {
+            area.limit = limit;
+        }
+        if let Some(price) = price {
+            area.land_price = price.parse().unwrap();
+        }
+        if let Some(open_time) = open_time {
+            area.open_time = open_time;
+        }
+        if let Some(close_time) = close_time {
+            area.close_time = close_time;
+        }
+        if let Some(max_per_account) = max_per_account {
+            area.max_per_account = Some(max_per_account).filter(|max| *max > 0);
+        }
+        area.assert_valid();
+        self.area_metadata_by_id.insert(&area_hash, &area);
+    }
+
+    pub fn pause_area(&mut self, name: String) {
+        self.assert_role(Role::Pauser);
+        let (area_hash, mut area) = self.internal_get_area(&name);
+        assert!(!area.closed, "Area is closed");
+        assert!(!area.paused, "Area is already paused");
+        area.paused = true;
+        self.area_metadata_by_id.insert(&area_hash, &area);
+    }
+
+    pub fn resume_area(&mut self, name: String) {
+        self.assert_role(Role::Pauser);
+        let (area_hash, mut area) = self.internal_get_area(&name);
+        assert!(!area.closed, "Area is closed");
+        assert!(area.paused, "Area is not paused");
+        area.paused = false;
+        self.area_metadata_by_id.insert(&area_hash, &area);
+    }
+
+    /// Ends sales in the area for good. Minted lands are not affected.
+    pub fn close_area(&mut self, name: String) {
+        self.assert_role(Role::AreaManager);
+        let (area_hash, mut area) = self.internal_get_area(&name);
+        assert!(!area.closed, "Area is closed");
+        area.closed = true;
+        self.area_metadata_by_id.insert(&area_hash, &area);
+    }
+
+    /// Removes an area that has not sold any land yet, e.g. one created by mistake. Its prime
+    /// cells and presale allowlist are keyed by name, so they must be removed first.
+    pub fn delete_area(&mut self, name: String) {
+        self.assert_role(Role::AreaManager);
+        let (area_hash, area) = self.internal_get_area(&name);
+        assert!(area.land_sold == 0, "Cannot delete an area with lands sold");
+        assert!(
+            area.prime_cells == 0,
+            "Cannot delete an area with prime cells"
+        );
+        assert!(
+            area.presale
+                .is_none_or(|presale| presale.allowlist_size == 0),
+            "Cannot delete an area with a presale allowlist"
+        );
+        self.area_metadata_by_id.remove(&area_hash);
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_get_area(&self, name: &str) -> (String, AreaMetadata) {
+        let area_hash = hash_area_name(name);
+        let area = self
+            .area_metadata_by_id
+            .get(&area_hash)
+            .expect("Area no exist.");
+        (area_hash, area)
+    }
+}
@@ -0,0 +1,158 @@
+use crate::*;
+
+/// One cell of an area's grid, as returned by `get_area_map`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Parcel {
+    pub x: u32,
+    pub y: u32,
+    /// Land minted on the cell, `None` while the cell is free.
+    pub token_id: Option<TokenId>,
+    /// Extra price of the cell if the owner marked it as prime.
+    pub premium: Option<U128>,
+}
+
+impl AreaMetadata {
+    pub fn cell_count(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
+    }
+
+    fn assert_on_grid(&self, x: u32, y: u32) {
+        assert!(
+            x < self.width && y < self.height,
+            "Cell ({}, {}) is outside the area grid",
+            x,
+            y
+        );
+    }
+
+    /// Coordinates of the cell at row-major `index`.
+    fn cell_at(&self, index: u64) -> (u32, u32) {
+        let width = u64::from(self.width);
+        ((index % width) as u32, (index / width) as u32)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Marks free `cells` as prime, charging `premium` on top of the land price when they are
+    /// bought with `buy_land_at`. Prime cells are never auto-assigned, so the regular free cells
+    /// must still cover the lands left to sell.
+    pub fn set_prime_cells(&mut self, name: String, cells: Vec<(u32, u32)>, premium: String) {
+        self.assert_role(Role::AreaManager);
+        let (area_hash, mut area) = self.internal_get_area(&name);
+        let premium: Balance = premium.parse().unwrap();
+        assert!(premium > 0, "Premium must be positive");
+
+        for (x, y) in cells {
+            area.assert_on_grid(x, y);
+            let key = (area_hash.clone(), x, y);
+            assert!(
+                !self.land_by_cell.contains_key(&key),
+                "Cell ({}, {}) is already taken",
+                x,
+                y
+            );
+            if self.prime_cell_premiums.insert(&key, &premium).is_none() {
+                area.prime_cells += 1;
+            }
+        }
+        area.assert_valid();
+        self.area_metadata_by_id.insert(&area_hash, &area);
+    }
+
+    /// Turns `cells` back into regular cells that sell at the land price.
+    pub fn remove_prime_cells(&mut self, name: String, cells: Vec<(u32, u32)>) {
+        self.assert_role(Role::AreaManager);
+        let (area_hash, mut area) = self.internal_get_area(&name);
+
+        for (x, y) in cells {
+            area.assert_on_grid(x, y);
+            let key = (area_hash.clone(), x, y);
+            if self.prime_cell_premiums.remove(&key).is_some()
+                && !self.land_by_cell.contains_key(&key)
+            {
+                area.prime_cells -= 1;
+                // The cell may have been skipped by auto-assignment, make it reachable again.
+                let index = u64::from(y) * u64::from(area.width) + u64::from(x);
+                area.next_cell = area.next_cell.min(index);
+            }
+        }
+        self.area_metadata_by_id.insert(&area_hash, &area);
+    }
+
+    pub fn get_prime_cell_premium(&self, name: String, x: u32, y: u32) -> Option<U128> {
+        self.prime_cell_premiums
+            .get(&(hash_area_name(&name), x, y))
+            .map(U128)
+    }
+
+    pub fn get_land_at(&self, name: String, x: u32, y: u32) -> Option<JsonToken<TokenMetadata>> {
+        let token_id = self.land_by_cell.get(&(hash_area_name(&name), x, y))?;
+        self.tokens.nft_token(token_id)
+    }
+
+    /// Occupancy of the area's cells in row-major order, starting at cell `from_index`.
+    pub fn get_area_map(&self, name: String, from_index: U64, limit: U64) -> Vec<Parcel> {
+        let (area_hash, area) = self.internal_get_area(&name);
+        let end = area.cell_count().min(from_index.0.saturating_add(limit.0));
+        (from_index.0..end)
+            .map(|index| {
+                let (x, y) = area.cell_at(index);
+                let key = (area_hash.clone(), x, y);
+                Parcel {
+                    x,
+                    y,
+                    token_id: self.land_by_cell.get(&key),
+                    premium: self.prime_cell_premiums.get(&key).map(U128),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Contract {
+    /// Records `token_id` on `cell`, or on the first free cell in row-major order if `None`,
+    /// and returns the coordinates used.
+    pub(crate) fn internal_claim_cell(
+        &mut self,
+        area: &mut AreaMetadata,
+        area_hash: &str,
+        cell: Option<(u32, u32)>,
+        token_id: &TokenId,
+    ) -> (u32, u32) {
+        let (x, y) = match cell {
+            Some((x, y)) => {
+                area.assert_on_grid(x, y);
+                (x, y)
+            }
+            None => loop {
+                assert!(
+                    area.next_cell < area.cell_count(),
+                    "No free cells left in this area"
+                );
+                let (x, y) = area.cell_at(area.next_cell);
+                area.next_cell += 1;
+                let key = (area_hash.to_string(), x, y);
+                if !self.land_by_cell.contains_key(&key)
+                    && !self.prime_cell_premiums.contains_key(&key)
+                {
+                    break (x, y);
+                }
+            },
+        };
+
+        let key = (area_hash.to_string(), x, y);
+        assert!(
+            !self.land_by_cell.contains_key(&key),
+            "Cell ({}, {}) is already taken",
+            x,
+            y
+        );
+        if self.prime_cell_premiums.contains_key(&key) {
+            area.prime_cells -= 1;
+        }
+        self.land_by_cell.insert(&key, token_id);
+        (x, y)
+    }
+}
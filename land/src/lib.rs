@@ -1,244 +1,2320 @@
-use near_sdk::Timestamp;
-use std::cmp::min;
-use std::collections::HashMap;
-use std::convert::TryFrom;
-
-use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
-use near_sdk::json_types::{Base64VecU8, ValidAccountId, U128, U64};
-use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{
-    env, log, near_bindgen, AccountId, Balance, CryptoHash, PanicOnDefault, Promise, StorageUsage,
-};
-
-pub use crate::enumerable::*;
-use crate::internal::*;
-pub use crate::metadata::*;
-pub use crate::mint::*;
-pub use crate::nft_core::*;
-pub use crate::token::*;
-
-mod enumerable;
-mod internal;
-mod metadata;
-mod mint;
-mod nft_core;
-mod token;
-
-const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
-const MINT_FEE: Balance = 2_000_000_000_000_000_000_000_0;
-
-near_sdk::setup_alloc!();
-
-#[near_bindgen]
-#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
-pub struct Contract {
-    pub tokens_per_owner: LookupMap<AccountId, UnorderedSet<TokenId>>,
-
-    pub tokens_by_id: LookupMap<TokenId, Token>,
-
-    pub token_metadata_by_id: UnorderedMap<TokenId, TokenMetadata>,
-
-    pub owner_id: AccountId,
-
-    /// The storage size in bytes for one account.
-    pub extra_storage_in_bytes_per_token: StorageUsage,
-
-    pub metadata: LazyOption<NFTMetadata>,
-
-    pub area_metadata_by_id: UnorderedMap<String, AreaMetadata>,
-}
-
-/// Helper structure to for keys of the persistent collections.
-#[derive(BorshSerialize)]
-pub enum StorageKey {
-    TokensPerOwner,
-    TokenPerOwnerInner { account_id_hash: CryptoHash },
-    TokensById,
-    TokenMetadataById,
-    AreaMetadataById,
-    NftMetadata,
-    TokensPerType,
-    TokensPerTypeInner { token_type_hash: CryptoHash },
-    TokenTypesLocked,
-}
-
-#[near_bindgen]
-impl Contract {
-    #[init]
-    pub fn new_default_meta(owner_id: ValidAccountId) -> Self {
-        Self::new(
-            owner_id,
-            NFTMetadata {
-                spec: "nft-1.0.0".to_string(),
-                name: "The metaverse".to_string(),
-                symbol: "Land".to_string(),
-                icon: Some(DATA_IMAGE_SVG_NEAR_ICON.to_string()),
-                base_uri: None,
-                reference: None,
-                reference_hash: None,
-            },
-        )
-    }
-    #[init]
-    pub fn new(owner_id: ValidAccountId, metadata: NFTMetadata) -> Self {
-        let mut this = Self {
-            tokens_per_owner: LookupMap::new(StorageKey::TokensPerOwner.try_to_vec().unwrap()),
-            tokens_by_id: LookupMap::new(StorageKey::TokensById.try_to_vec().unwrap()),
-            token_metadata_by_id: UnorderedMap::new(
-                StorageKey::TokenMetadataById.try_to_vec().unwrap(),
-            ),
-            owner_id: owner_id.into(),
-            extra_storage_in_bytes_per_token: 0,
-            metadata: LazyOption::new(
-                StorageKey::NftMetadata.try_to_vec().unwrap(),
-                Some(&metadata),
-            ),
-            area_metadata_by_id: UnorderedMap::new(
-                StorageKey::AreaMetadataById.try_to_vec().unwrap(),
-            ),
-        };
-
-        this.measure_min_token_storage_cost();
-
-        this
-    }
-
-    fn measure_min_token_storage_cost(&mut self) {
-        let initial_storage_usage = env::storage_usage();
-        let tmp_account_id = "a".repeat(64);
-        let u = UnorderedSet::new(
-            StorageKey::TokenPerOwnerInner {
-                account_id_hash: hash_account_id(&tmp_account_id),
-            }
-            .try_to_vec()
-            .unwrap(),
-        );
-        self.tokens_per_owner.insert(&tmp_account_id, &u);
-
-        let tokens_per_owner_entry_in_bytes = env::storage_usage() - initial_storage_usage;
-        let owner_id_extra_cost_in_bytes = (tmp_account_id.len() - self.owner_id.len()) as u64;
-
-        self.extra_storage_in_bytes_per_token =
-            tokens_per_owner_entry_in_bytes + owner_id_extra_cost_in_bytes;
-
-        self.tokens_per_owner.remove(&tmp_account_id);
-    }
-
-    // View method
-    pub fn get_area(&self, name: String) -> Option<AreaMetadata> {
-        let hash = hex::encode(&env::sha256(name.as_bytes()));
-        self.area_metadata_by_id.get(&hash)
-    }
-
-    // Call method
-    pub fn open_area(
-        &mut self,
-        name: String,
-        limit: u64,
-        price: String,
-        open_time: Timestamp,
-        close_time: Timestamp,
-    ) {
-        assert!(
-            env::predecessor_account_id() == self.owner_id,
-            "Caller is not owner."
-        );
-
-        let hash = hex::encode(&env::sha256(name.as_bytes()));
-        self.area_metadata_by_id.insert(
-            &hash.clone(),
-            &AreaMetadata {
-                name: name,
-                limit: limit,
-                land_sold: 0u64,
-                land_price: price.parse().unwrap(),
-                open_time: open_time,
-                close_time: close_time,
-            },
-        );
-    }
-
-    #[payable]
-    pub fn buy_land(&mut self, name: String) {
-        let area_data = self.get_area(name.clone());
-
-        assert!(area_data != None, "Area no exist.");
-        let mut area = area_data.unwrap();
-
-        assert!(
-            env::block_timestamp() > area.open_time,
-            "This area has not started selling lands yet"
-        );
-        log!(
-            "{}",
-            format!(
-                "close time: {}, time block: {}",
-                area.close_time,
-                env::block_timestamp()
-            )
-        );
-        assert!(
-            env::block_timestamp() > area.close_time,
-            "This area has ended lands sales"
-        );
-        assert!(area.land_sold < area.limit, "All lands are sold out");
-        assert!(
-            env::attached_deposit() >= area.land_price + MINT_FEE,
-            "Please deposit price equal land price + mint fee, excess mint fee will be refund !"
-        );
-
-        let new_name = name.clone() + " #" + &area.land_sold.to_string();
-        let token_id = hex::encode(&env::sha256(new_name.as_bytes()));
-
-        let area_hash = hex::encode(&env::sha256(name.as_bytes()));
-
-        area.land_sold += 1;
-        self.area_metadata_by_id.insert(&area_hash, &area);
-
-        let mining_efficiency: u32 = 80 + ((env::block_timestamp() % 100) as u32);
-        let mining_power: u32 = 33 + ((env::block_timestamp() % 15) as u32) + ((area.land_sold % 15) as u32);
-
-        let token: TokenMetadata = TokenMetadata {
-            title: Some(String::from(new_name.clone())),
-            description: Some(String::from(new_name)),
-            media: Some(String::from("https://res.cloudinary.com/dcrbaasbt/image/upload/v1637838225/257513804_224195603181581_4280639743210185776_n_nwqzoz.png")),
-            media_hash: None,
-            copies: Some(1),
-            issued_at: Some(env::block_timestamp()),
-            city: Some(name),
-            location: Some(String::from("10, 20")),
-            rare: Some(String::from("R")),
-            mining_efficiency: Some(mining_efficiency),
-            mining_power: Some(mining_power),
-        };
-
-        self.nft_mint(
-            Some(token_id),
-            token,
-            Some(HashMap::new()),
-            Some(ValidAccountId::try_from(env::predecessor_account_id()).unwrap()),
-            area.land_price,
-        )
-    }
-
-    pub fn get_land(&self, name: String) -> Option<TokenMetadata> {
-        let hash = hex::encode(&env::sha256(name.as_bytes()));
-        self.token_metadata_by_id.get(&hash)
-    }
-
-    pub fn get_lands_by_owner(&self, owner_id: AccountId) -> Vec<TokenMetadata> {
-        let token_ids = self
-            .tokens_per_owner
-            .get(&owner_id)
-            .unwrap_or_else(|| UnorderedSet::new(b"".to_vec()));
-        token_ids
-            .iter()
-            .map(|token_id| self.token_metadata_by_id.get(&token_id).unwrap())
-            .collect()
-    }
-
-    pub fn get_all_areas(&self) -> Vec<AreaMetadata> {
-        self.area_metadata_by_id.values().collect()
-    }
-}
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LazyOption, LookupMap, LookupSet, UnorderedMap};
+use near_sdk::json_types::{Base64VecU8, ValidAccountId, U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{
+    env, near_bindgen, AccountId, Balance, PanicOnDefault, Promise, PromiseOrValue, StorageUsage,
+    Timestamp,
+};
+
+pub use crate::grid::*;
+use crate::internal::*;
+pub use crate::metadata::*;
+pub use crate::mining::*;
+pub use crate::placement::*;
+pub use crate::stats::*;
+pub use scity_access::*;
+pub use scity_common::*;
+pub use scity_nft_core::*;
+
+mod area;
+mod grid;
+mod internal;
+mod metadata;
+mod mining;
+mod mint;
+mod placement;
+mod presale;
+mod stats;
+
+const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
+const MINT_FEE: Balance = 20_000_000_000_000_000_000_000;
+
+near_sdk::setup_alloc!();
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    pub tokens: NonFungibleToken<TokenMetadata>,
+
+    pub owner_id: AccountId,
+
+    /// Account proposed as the next owner, until it accepts.
+    pub pending_owner_id: Option<AccountId>,
+
+    pub access: AccessControl,
+
+    pub pausable: Pausable,
+
+    pub metadata: LazyOption<NFTMetadata>,
+
+    pub area_metadata_by_id: UnorderedMap<String, AreaMetadata>,
+
+    /// Accounts allowed to buy during an area's presale, keyed by (area hash, account).
+    pub presale_allowlist: LookupSet<(String, AccountId)>,
+
+    /// Lands bought during an area's presale, keyed by (area hash, account).
+    pub presale_purchases: LookupMap<(String, AccountId), u64>,
+
+    /// Lands bought in each area across all sale phases, keyed by (area hash, account).
+    pub purchases_per_account: LookupMap<(String, AccountId), u64>,
+
+    /// Land minted on each grid cell, keyed by (area hash, x, y).
+    pub land_by_cell: LookupMap<(String, u32, u32), TokenId>,
+
+    /// Extra price of owner-designated prime cells, keyed by (area hash, x, y).
+    pub prime_cell_premiums: LookupMap<(String, u32, u32), Balance>,
+
+    /// Sale revenue across all areas and direct mints.
+    pub proceeds: ProceedsLedger,
+
+    pub treasury: Option<TreasuryConfig>,
+
+    pub config: ContractConfig,
+
+    /// Company placed on each land, held in escrow by this contract.
+    pub company_by_land: LookupMap<TokenId, TokenId>,
+
+    pub land_by_company: LookupMap<TokenId, TokenId>,
+
+    /// Account that placed each company, who gets it back when it is unplaced.
+    pub company_owners: LookupMap<TokenId, AccountId>,
+
+    /// Companies whose return failed after their land was taken, per owner, until claimed.
+    pub pending_companies: LookupMap<AccountId, Vec<TokenId>>,
+
+    pub emission: Option<EmissionConfig>,
+
+    /// Reward emitted per unit of mining weight since the contract was created.
+    pub reward_index: Balance,
+
+    /// When `reward_index` was last brought up to date, in seconds.
+    pub reward_index_updated: u64,
+
+    /// Lands mining with a company placed on them.
+    pub mining_positions: LookupMap<TokenId, MiningPosition>,
+
+    /// Mined rewards of each land not claimed yet.
+    pub unclaimed_rewards: LookupMap<TokenId, Balance>,
+}
+
+/// Helper structure to for keys of the persistent collections.
+#[derive(BorshSerialize)]
+pub enum StorageKey {
+    NonFungibleToken,
+    AreaMetadataById,
+    NftMetadata,
+    PresaleAllowlist,
+    PresalePurchases,
+    PurchasesPerAccount,
+    LandByCell,
+    PrimeCellPremiums,
+    AccessControl,
+    CompanyByLand,
+    LandByCompany,
+    MiningPositions,
+    UnclaimedRewards,
+    PendingCompanies,
+    CompanyOwners,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new_default_meta(owner_id: ValidAccountId, config: ContractConfig) -> Self {
+        Self::new(
+            owner_id,
+            NFTMetadata {
+                spec: "nft-1.0.0".to_string(),
+                name: "The metaverse".to_string(),
+                symbol: "Land".to_string(),
+                icon: Some(DATA_IMAGE_SVG_NEAR_ICON.to_string()),
+                base_uri: None,
+                reference: None,
+                reference_hash: None,
+            },
+            config,
+        )
+    }
+    #[init]
+    pub fn new(owner_id: ValidAccountId, metadata: NFTMetadata, config: ContractConfig) -> Self {
+        let owner_id: AccountId = owner_id.into();
+        Self {
+            tokens: NonFungibleToken::new(
+                StorageKey::NonFungibleToken.try_to_vec().unwrap(),
+                &owner_id,
+            ),
+            owner_id,
+            pending_owner_id: None,
+            access: AccessControl::new(StorageKey::AccessControl.try_to_vec().unwrap()),
+            pausable: Pausable::default(),
+            metadata: LazyOption::new(
+                StorageKey::NftMetadata.try_to_vec().unwrap(),
+                Some(&metadata),
+            ),
+            area_metadata_by_id: UnorderedMap::new(
+                StorageKey::AreaMetadataById.try_to_vec().unwrap(),
+            ),
+            presale_allowlist: LookupSet::new(StorageKey::PresaleAllowlist.try_to_vec().unwrap()),
+            presale_purchases: LookupMap::new(StorageKey::PresalePurchases.try_to_vec().unwrap()),
+            purchases_per_account: LookupMap::new(
+                StorageKey::PurchasesPerAccount.try_to_vec().unwrap(),
+            ),
+            land_by_cell: LookupMap::new(StorageKey::LandByCell.try_to_vec().unwrap()),
+            prime_cell_premiums: LookupMap::new(
+                StorageKey::PrimeCellPremiums.try_to_vec().unwrap(),
+            ),
+            proceeds: ProceedsLedger::default(),
+            treasury: None,
+            config,
+            company_by_land: LookupMap::new(StorageKey::CompanyByLand.try_to_vec().unwrap()),
+            land_by_company: LookupMap::new(StorageKey::LandByCompany.try_to_vec().unwrap()),
+            company_owners: LookupMap::new(StorageKey::CompanyOwners.try_to_vec().unwrap()),
+            pending_companies: LookupMap::new(StorageKey::PendingCompanies.try_to_vec().unwrap()),
+            emission: None,
+            reward_index: 0,
+            reward_index_updated: env::block_timestamp() / 1_000_000_000,
+            mining_positions: LookupMap::new(StorageKey::MiningPositions.try_to_vec().unwrap()),
+            unclaimed_rewards: LookupMap::new(StorageKey::UnclaimedRewards.try_to_vec().unwrap()),
+        }
+    }
+
+    // View method
+    pub fn get_area(&self, name: String) -> Option<AreaMetadata> {
+        self.area_metadata_by_id.get(&hash_area_name(&name))
+    }
+
+    pub fn get_area_phase(&self, name: String) -> Option<SalePhase> {
+        self.get_area(name)
+            .map(|area| area.phase(env::block_timestamp()))
+    }
+
+    pub fn get_area_proceeds(&self, name: String) -> U128 {
+        U128(self.internal_get_area(&name).1.proceeds)
+    }
+
+    // Call method
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_area(
+        &mut self,
+        name: String,
+        limit: u64,
+        width: u32,
+        height: u32,
+        price: String,
+        open_time: Timestamp,
+        close_time: Timestamp,
+        max_per_account: Option<u64>,
+    ) {
+        self.assert_role(Role::AreaManager);
+        let area_hash = hash_area_name(&name);
+        assert!(
+            self.area_metadata_by_id.get(&area_hash).is_none(),
+            "Area {} already exists",
+            name
+        );
+
+        let area = AreaMetadata {
+            name,
+            limit,
+            width,
+            height,
+            next_cell: 0,
+            prime_cells: 0,
+            land_sold: 0u64,
+            land_price: price.parse().unwrap(),
+            open_time,
+            close_time,
+            max_per_account,
+            paused: false,
+            closed: false,
+            presale: None,
+            stats: LandStatsConfig::default(),
+            rarity_minted: HashMap::new(),
+            proceeds: 0,
+        };
+        area.assert_valid();
+        self.area_metadata_by_id.insert(&area_hash, &area);
+    }
+
+    #[payable]
+    pub fn buy_land(&mut self, name: String) {
+        self.internal_buy_lands(name, 1, None);
+    }
+
+    /// Mints the land on cell `(x, y)` of the area to the caller, if the cell is still free.
+    /// Prime cells cost their premium on top of the land price.
+    #[payable]
+    pub fn buy_land_at(&mut self, name: String, x: u32, y: u32) {
+        self.internal_buy_lands(name, 1, Some((x, y)));
+    }
+
+    /// Mints `count` lands to the caller, charging storage and price for all of them at once.
+    #[payable]
+    pub fn buy_lands(&mut self, name: String, count: u64) {
+        self.internal_buy_lands(name, count, None);
+    }
+
+    pub fn get_account_purchases(&self, name: String, account_id: ValidAccountId) -> u64 {
+        self.purchases_per_account
+            .get(&(hash_area_name(&name), account_id.into()))
+            .unwrap_or(0)
+    }
+
+    pub fn get_land(&self, name: String) -> Option<TokenMetadata> {
+        let hash = hex::encode(env::sha256(name.as_bytes()));
+        self.tokens.token_metadata_by_id.get(&hash)
+    }
+
+    pub fn get_lands_by_owner(&self, owner_id: AccountId) -> Vec<TokenMetadata> {
+        self.tokens.token_metadata_for_owner(&owner_id)
+    }
+
+    pub fn get_all_areas(&self) -> Vec<AreaMetadata> {
+        self.area_metadata_by_id.values().collect()
+    }
+
+    /// Mints `count` lands to the caller on `cell`, or on auto-assigned free cells if `None`.
+    /// The caller pays for all the storage the purchase adds, including its purchase records.
+    fn internal_buy_lands(&mut self, name: String, count: u64, cell: Option<(u32, u32)>) {
+        self.pausable.assert_not_paused(Feature::Sales);
+        let (area_hash, mut area) = self.internal_get_area(&name);
+        assert!(count > 0, "Must buy at least one land");
+
+        let initial_storage_usage = env::storage_usage();
+        let buyer_id = env::predecessor_account_id();
+        let land_price = match area.phase(env::block_timestamp()) {
+            SalePhase::Upcoming => env::panic(b"This area has not started selling lands yet"),
+            SalePhase::Presale => {
+                self.internal_record_presale_purchase(&area, &area_hash, &buyer_id, count)
+            }
+            SalePhase::Paused => env::panic(b"Lands sales are paused in this area"),
+            SalePhase::Closed => env::panic(b"This area has ended lands sales"),
+            SalePhase::SoldOut => env::panic(b"All lands are sold out"),
+            SalePhase::Open => area.land_price,
+        };
+        assert!(
+            count <= area.limit - area.land_sold,
+            "Only {} lands are left in this area",
+            area.limit - area.land_sold
+        );
+
+        let purchase_key = (area_hash.clone(), buyer_id.clone());
+        let purchases = self.purchases_per_account.get(&purchase_key).unwrap_or(0) + count;
+        if let Some(max_per_account) = area.max_per_account {
+            assert!(
+                purchases <= max_per_account,
+                "Purchase limit per account reached"
+            );
+        }
+        self.purchases_per_account.insert(&purchase_key, &purchases);
+
+        let premium = cell
+            .and_then(|(x, y)| self.prime_cell_premiums.get(&(area_hash.clone(), x, y)))
+            .unwrap_or(0);
+        let total_price = land_price * Balance::from(count) + premium;
+        assert!(
+            env::attached_deposit() >= total_price + MINT_FEE * Balance::from(count),
+            "Please deposit price equal land price + mint fee, excess mint fee will be refund !"
+        );
+
+        let seed = env::random_seed();
+        for _ in 0..count {
+            let new_name = name.clone() + " #" + &area.land_sold.to_string();
+            let token_id = hex::encode(env::sha256(new_name.as_bytes()));
+
+            area.land_sold += 1;
+
+            let (x, y) = self.internal_claim_cell(&mut area, &area_hash, cell, &token_id);
+
+            let stats = area.stats.roll(&seed, &token_id, &area.rarity_minted);
+            *area.rarity_minted.entry(stats.rare.clone()).or_insert(0) += 1;
+
+            let token: TokenMetadata = TokenMetadata {
+                title: Some(new_name.clone()),
+                description: Some(new_name),
+                media: Some(String::from("https://res.cloudinary.com/dcrbaasbt/image/upload/v1637838225/257513804_224195603181581_4280639743210185776_n_nwqzoz.png")),
+                media_hash: None,
+                copies: Some(1),
+                issued_at: Some(env::block_timestamp()),
+                city: Some(name.clone()),
+                location: Some(format!("{}, {}", x, y)),
+                rare: Some(stats.rare),
+                mining_efficiency: Some(stats.mining_efficiency),
+                mining_power: Some(stats.mining_power),
+            };
+
+            self.internal_nft_mint(
+                Some(token_id),
+                token,
+                Some(HashMap::new()),
+                Some(ValidAccountId::try_from(buyer_id.clone()).unwrap()),
+            );
+        }
+        area.proceeds += total_price;
+        self.area_metadata_by_id.insert(&area_hash, &area);
+        self.proceeds.collected += total_price;
+
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+        refund_deposit_buy_land(storage_used, total_price);
+    }
+}
+
+scity_nft_core::impl_non_fungible_token_core!(
+    Contract,
+    tokens,
+    TokenMetadata,
+    assert_transfers_not_paused
+);
+scity_nft_core::impl_non_fungible_token_payout!(Contract, tokens, assert_transfers_not_paused);
+scity_nft_core::impl_non_fungible_token_enumeration!(Contract, tokens, TokenMetadata);
+scity_access::impl_access_control!(Contract, access, owner_id);
+scity_access::impl_ownership!(Contract, owner_id, pending_owner_id);
+scity_access::impl_pausable!(Contract, pausable);
+scity_common::impl_contract_config!(Contract, config);
+scity_common::impl_treasury!(Contract, proceeds, treasury);
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::testing_env_with_promise_results;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain, PromiseResult};
+
+    use super::*;
+
+    const OPEN_TIME: Timestamp = 1_645_030_800_000_000_000;
+    const CLOSE_TIME: Timestamp = 1_650_128_399_000_000_000;
+    const LAND_PRICE: Balance = 100_000_000_000_000_000_000_000;
+    const GRID_SIZE: u32 = 4;
+
+    fn get_context(predecessor_account_id: ValidAccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id);
+        builder
+    }
+
+    fn config() -> ContractConfig {
+        ContractConfig {
+            box_contract_id: ValidAccountId::try_from("box.scity.testnet").unwrap(),
+            land_contract_id: accounts(0),
+            company_contract_id: ValidAccountId::try_from("company.scity.testnet").unwrap(),
+        }
+    }
+
+    fn setup(limit: u64) -> (VMContextBuilder, Contract) {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), config());
+        contract.create_area(
+            "tokyo".to_string(),
+            limit,
+            GRID_SIZE,
+            GRID_SIZE,
+            LAND_PRICE.to_string(),
+            OPEN_TIME,
+            CLOSE_TIME,
+            None,
+        );
+        (context, contract)
+    }
+
+    fn buy_land_at(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        buyer: ValidAccountId,
+        now: Timestamp,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(buyer)
+            .block_timestamp(now)
+            .attached_deposit(LAND_PRICE + MINT_FEE)
+            .build());
+        contract.buy_land("tokyo".to_string());
+    }
+
+    fn phase_at(context: &mut VMContextBuilder, contract: &Contract, now: Timestamp) -> SalePhase {
+        testing_env!(context.block_timestamp(now).is_view(true).build());
+        let phase = contract.get_area_phase("tokyo".to_string()).unwrap();
+        testing_env!(context.is_view(false).build());
+        phase
+    }
+
+    #[test]
+    fn test_phase_transitions() {
+        let (mut context, contract) = setup(1);
+        assert_eq!(
+            phase_at(&mut context, &contract, OPEN_TIME - 1),
+            SalePhase::Upcoming
+        );
+        assert_eq!(
+            phase_at(&mut context, &contract, OPEN_TIME),
+            SalePhase::Open
+        );
+        assert_eq!(
+            phase_at(&mut context, &contract, CLOSE_TIME - 1),
+            SalePhase::Open
+        );
+        assert_eq!(
+            phase_at(&mut context, &contract, CLOSE_TIME),
+            SalePhase::Closed
+        );
+        assert!(contract.get_area_phase("osaka".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_phase_sold_out() {
+        let (mut context, mut contract) = setup(1);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        assert_eq!(contract.get_area("tokyo".to_string()).unwrap().land_sold, 1);
+        assert_eq!(
+            phase_at(&mut context, &contract, OPEN_TIME),
+            SalePhase::SoldOut
+        );
+        assert_eq!(
+            phase_at(&mut context, &contract, CLOSE_TIME),
+            SalePhase::SoldOut
+        );
+        assert_eq!(contract.get_lands_by_owner(accounts(1).into()).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "This area has not started selling lands yet")]
+    fn test_buy_land_upcoming() {
+        let (mut context, mut contract) = setup(1);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME - 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "This area has ended lands sales")]
+    fn test_buy_land_closed() {
+        let (mut context, mut contract) = setup(1);
+        buy_land_at(&mut context, &mut contract, accounts(1), CLOSE_TIME);
+    }
+
+    #[test]
+    #[should_panic(expected = "All lands are sold out")]
+    fn test_buy_land_sold_out() {
+        let (mut context, mut contract) = setup(1);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Open time must be before close time")]
+    fn test_create_area_invalid_window() {
+        let (_, mut contract) = setup(1);
+        contract.create_area(
+            "osaka".to_string(),
+            1,
+            GRID_SIZE,
+            GRID_SIZE,
+            LAND_PRICE.to_string(),
+            CLOSE_TIME,
+            OPEN_TIME,
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Sale times must be in nanoseconds")]
+    fn test_create_area_seconds() {
+        let (_, mut contract) = setup(1);
+        contract.create_area(
+            "osaka".to_string(),
+            1,
+            GRID_SIZE,
+            GRID_SIZE,
+            LAND_PRICE.to_string(),
+            1_645_030_800,
+            1_650_128_399,
+            None,
+        );
+    }
+
+    const PRESALE_TIME: Timestamp = OPEN_TIME - 1_000_000_000;
+    const PRESALE_PRICE: Balance = LAND_PRICE / 2;
+
+    fn setup_presale(limit: u64) -> (VMContextBuilder, Contract) {
+        let (mut context, mut contract) = setup(limit);
+        contract.set_area_presale(
+            "tokyo".to_string(),
+            PRESALE_PRICE.to_string(),
+            1,
+            PRESALE_TIME,
+        );
+        contract.add_to_presale_allowlist("tokyo".to_string(), vec![accounts(1), accounts(2)]);
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_presale_config() {
+        let (_, mut contract) = setup_presale(10);
+        contract.add_to_presale_allowlist("tokyo".to_string(), vec![accounts(2), accounts(3)]);
+        contract.remove_from_presale_allowlist("tokyo".to_string(), vec![accounts(1)]);
+
+        let presale = contract
+            .get_area("tokyo".to_string())
+            .unwrap()
+            .presale
+            .unwrap();
+        assert_eq!(presale.land_price, PRESALE_PRICE);
+        assert_eq!(presale.limit_per_account, 1);
+        assert_eq!(presale.open_time, PRESALE_TIME);
+        assert_eq!(presale.allowlist_size, 2);
+        assert!(!contract.is_on_presale_allowlist("tokyo".to_string(), accounts(1)));
+        assert!(contract.is_on_presale_allowlist("tokyo".to_string(), accounts(3)));
+    }
+
+    #[test]
+    fn test_presale_phase() {
+        let (mut context, mut contract) = setup_presale(1);
+        assert_eq!(
+            phase_at(&mut context, &contract, PRESALE_TIME - 1),
+            SalePhase::Upcoming
+        );
+        assert_eq!(
+            phase_at(&mut context, &contract, PRESALE_TIME),
+            SalePhase::Presale
+        );
+        assert_eq!(
+            phase_at(&mut context, &contract, OPEN_TIME - 1),
+            SalePhase::Presale
+        );
+        assert_eq!(
+            phase_at(&mut context, &contract, OPEN_TIME),
+            SalePhase::Open
+        );
+
+        // An area sold out during the presale stays sold out.
+        buy_land_at(&mut context, &mut contract, accounts(1), PRESALE_TIME);
+        assert_eq!(
+            phase_at(&mut context, &contract, OPEN_TIME - 1),
+            SalePhase::SoldOut
+        );
+        assert_eq!(
+            phase_at(&mut context, &contract, OPEN_TIME),
+            SalePhase::SoldOut
+        );
+    }
+
+    #[test]
+    fn test_presale_buy_land() {
+        let (mut context, mut contract) = setup_presale(10);
+        buy_land_at(&mut context, &mut contract, accounts(1), PRESALE_TIME);
+        assert_eq!(
+            contract.get_presale_purchases("tokyo".to_string(), accounts(1)),
+            1
+        );
+        assert_eq!(contract.get_area("tokyo".to_string()).unwrap().land_sold, 1);
+
+        // The presale cap does not apply once the public sale opens.
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        assert_eq!(contract.get_lands_by_owner(accounts(1).into()).len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is not on the presale allowlist")]
+    fn test_presale_buy_land_not_allowlisted() {
+        let (mut context, mut contract) = setup_presale(10);
+        buy_land_at(&mut context, &mut contract, accounts(3), PRESALE_TIME);
+    }
+
+    #[test]
+    #[should_panic(expected = "Presale limit per account reached")]
+    fn test_presale_buy_land_over_limit() {
+        let (mut context, mut contract) = setup_presale(10);
+        buy_land_at(&mut context, &mut contract, accounts(1), PRESALE_TIME);
+        buy_land_at(&mut context, &mut contract, accounts(1), PRESALE_TIME + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Presale must open before the public sale")]
+    fn test_presale_after_public_open() {
+        let (_, mut contract) = setup(10);
+        contract.set_area_presale("tokyo".to_string(), PRESALE_PRICE.to_string(), 1, OPEN_TIME);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the area_manager role")]
+    fn test_presale_allowlist_without_role() {
+        let (mut context, mut contract) = setup_presale(10);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.add_to_presale_allowlist("tokyo".to_string(), vec![accounts(1)]);
+    }
+
+    fn buy_lands_at(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        buyer: ValidAccountId,
+        now: Timestamp,
+        count: u64,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(buyer)
+            .block_timestamp(now)
+            .attached_deposit((LAND_PRICE + MINT_FEE) * Balance::from(count))
+            .build());
+        contract.buy_lands("tokyo".to_string(), count);
+    }
+
+    fn setup_max_per_account(limit: u64, max_per_account: u64) -> (VMContextBuilder, Contract) {
+        let (context, mut contract) = setup(limit);
+        contract.update_area(
+            "tokyo".to_string(),
+            None,
+            None,
+            None,
+            None,
+            Some(max_per_account),
+        );
+        (context, contract)
+    }
+
+    #[test]
+    fn test_buy_lands() {
+        let (mut context, mut contract) = setup(10);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 3);
+
+        let lands = contract.get_lands_by_owner(accounts(1).into());
+        assert_eq!(lands.len(), 3);
+        assert_eq!(contract.get_area("tokyo".to_string()).unwrap().land_sold, 3);
+        assert_eq!(
+            contract.get_account_purchases("tokyo".to_string(), accounts(1)),
+            3
+        );
+        for i in 0..3 {
+            assert!(contract.get_land(format!("tokyo #{}", i)).is_some());
+        }
+    }
+
+    #[test]
+    fn test_buy_land_charges_purchase_storage() {
+        let (mut context, mut contract) = setup(3);
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(OPEN_TIME)
+            .attached_deposit(LAND_PRICE + MINT_FEE)
+            .build());
+        let storage_usage = env::storage_usage();
+        contract.buy_land("tokyo".to_string());
+
+        // Every byte the purchase adds, purchase records included, is charged to the buyer.
+        let storage_cost =
+            Balance::from(env::storage_usage() - storage_usage) * env::storage_byte_cost();
+        let receipt = format!("{:?}", get_created_receipts().last().unwrap());
+        assert!(receipt.contains(&format!(
+            "Transfer(TransferAction {{ deposit: {} }})",
+            MINT_FEE - storage_cost
+        )));
+    }
+
+    #[test]
+    #[should_panic(expected = "Please deposit price equal land price + mint fee")]
+    fn test_buy_lands_insufficient_deposit() {
+        let (mut context, mut contract) = setup(10);
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(OPEN_TIME)
+            .attached_deposit(LAND_PRICE * 3 + MINT_FEE * 3 - LAND_PRICE)
+            .build());
+        contract.buy_lands("tokyo".to_string(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only 2 lands are left in this area")]
+    fn test_buy_lands_over_remaining_supply() {
+        let (mut context, mut contract) = setup(2);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Must buy at least one land")]
+    fn test_buy_lands_zero() {
+        let (mut context, mut contract) = setup(2);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 0);
+    }
+
+    #[test]
+    fn test_max_per_account() {
+        let (mut context, mut contract) = setup_max_per_account(10, 3);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 2);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        buy_lands_at(&mut context, &mut contract, accounts(2), OPEN_TIME, 3);
+        assert_eq!(contract.get_area("tokyo".to_string()).unwrap().land_sold, 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "Purchase limit per account reached")]
+    fn test_max_per_account_reached() {
+        let (mut context, mut contract) = setup_max_per_account(10, 3);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 2);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Purchase limit per account reached")]
+    fn test_max_per_account_counts_presale() {
+        let (_, mut contract) = setup_max_per_account(10, 1);
+        contract.set_area_presale(
+            "tokyo".to_string(),
+            PRESALE_PRICE.to_string(),
+            1,
+            PRESALE_TIME,
+        );
+        contract.add_to_presale_allowlist("tokyo".to_string(), vec![accounts(1)]);
+        let mut context = get_context(accounts(1));
+        buy_land_at(&mut context, &mut contract, accounts(1), PRESALE_TIME);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+    }
+
+    fn rarity_tier(rare: &str, weight: u32, multiplier: u32, supply: Option<u64>) -> RarityTier {
+        RarityTier {
+            rare: rare.to_string(),
+            weight,
+            multiplier,
+            supply,
+        }
+    }
+
+    fn custom_stats() -> LandStatsConfig {
+        LandStatsConfig {
+            mining_efficiency: StatRange { min: 10, max: 20 },
+            mining_power: StatRange { min: 1, max: 3 },
+            rarity_table: vec![
+                rarity_tier("C", 70, MULTIPLIER_BASE, None),
+                rarity_tier("R", 25, MULTIPLIER_BASE, None),
+                rarity_tier("SSR", 5, MULTIPLIER_BASE, None),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_land_stats_replay_seed() {
+        let (mut context, mut contract) = setup(10);
+        contract.set_area_stats("tokyo".to_string(), custom_stats());
+        let seed = vec![7u8; 32];
+        testing_env!(context.random_seed(seed.clone()).build());
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 2);
+
+        for i in 0..2 {
+            let token_id = hex::encode(env::sha256(format!("tokyo #{}", i).as_bytes()));
+            let expected = custom_stats().roll(&seed, &token_id, &HashMap::new());
+            let land = contract.get_land(format!("tokyo #{}", i)).unwrap();
+            assert_eq!(land.rare, Some(expected.rare));
+            assert_eq!(land.mining_efficiency, Some(expected.mining_efficiency));
+            assert_eq!(land.mining_power, Some(expected.mining_power));
+        }
+    }
+
+    #[test]
+    fn test_land_stats_distribution_bounds() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let config = custom_stats();
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for i in 0..2_000u32 {
+            let seed = env::sha256(&i.to_le_bytes());
+            let stats = config.roll(&seed, "token", &HashMap::new());
+            assert!((10..=20).contains(&stats.mining_efficiency));
+            assert!((1..=3).contains(&stats.mining_power));
+            *counts.entry(stats.rare).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 3);
+        assert!(counts["C"] > counts["R"]);
+        assert!(counts["R"] > counts["SSR"]);
+    }
+
+    #[test]
+    fn test_land_stats_default_ranges() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let config = LandStatsConfig::default();
+        for i in 0..500u32 {
+            let stats = config.roll(&env::sha256(&i.to_le_bytes()), "token", &HashMap::new());
+            assert_eq!(stats.rare, "R");
+            assert!((80..=179).contains(&stats.mining_efficiency));
+            assert!((33..=61).contains(&stats.mining_power));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Stat range min must not exceed max")]
+    fn test_set_area_stats_invalid_range() {
+        let (_, mut contract) = setup(10);
+        let mut stats = custom_stats();
+        stats.mining_power = StatRange { min: 5, max: 4 };
+        contract.set_area_stats("tokyo".to_string(), stats);
+    }
+
+    #[test]
+    #[should_panic(expected = "Rarity table must have a positive weight")]
+    fn test_set_area_stats_empty_rarity_table() {
+        let (_, mut contract) = setup(10);
+        let mut stats = custom_stats();
+        stats.rarity_table = vec![];
+        contract.set_area_stats("tokyo".to_string(), stats);
+    }
+
+    #[test]
+    fn test_rarity_multiplier() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut config = custom_stats();
+        config.rarity_table = vec![rarity_tier("SSR", 1, 2 * MULTIPLIER_BASE, None)];
+        for i in 0..500u32 {
+            let stats = config.roll(&env::sha256(&i.to_le_bytes()), "token", &HashMap::new());
+            assert!((20..=40).contains(&stats.mining_efficiency));
+            assert!((2..=6).contains(&stats.mining_power));
+        }
+    }
+
+    #[test]
+    fn test_rarity_supply() {
+        let (mut context, mut contract) = setup(10);
+        let mut stats = custom_stats();
+        stats.rarity_table = vec![
+            rarity_tier("C", 1, MULTIPLIER_BASE, None),
+            rarity_tier("SSR", 1_000_000, 3 * MULTIPLIER_BASE, Some(1)),
+        ];
+        contract.set_area_stats("tokyo".to_string(), stats);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 4);
+
+        let supply = contract.get_area_rarity_supply("tokyo".to_string());
+        assert_eq!(supply[0].rare, "C");
+        assert_eq!(supply[0].remaining, None);
+        assert_eq!(supply[1].rare, "SSR");
+        assert_eq!(supply[1].minted, 1);
+        assert_eq!(supply[1].remaining, Some(0));
+        assert_eq!(supply[0].minted + supply[1].minted, 4);
+
+        let area = contract.get_area("tokyo".to_string()).unwrap();
+        assert_eq!(area.rarity_minted["SSR"], 1);
+        let ssr_lands = contract
+            .get_lands_by_owner(accounts(1).into())
+            .into_iter()
+            .filter(|land| land.rare.as_deref() == Some("SSR"))
+            .count();
+        assert_eq!(ssr_lands, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "All rarity tiers are sold out")]
+    fn test_rarity_supply_exhausted() {
+        let (mut context, mut contract) = setup(10);
+        let mut stats = custom_stats();
+        stats.rarity_table = vec![rarity_tier("SSR", 1, MULTIPLIER_BASE, Some(2))];
+        contract.set_area_stats("tokyo".to_string(), stats);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate rarity tier C")]
+    fn test_set_area_stats_duplicate_tier() {
+        let (_, mut contract) = setup(10);
+        let mut stats = custom_stats();
+        stats
+            .rarity_table
+            .push(rarity_tier("C", 1, MULTIPLIER_BASE, None));
+        contract.set_area_stats("tokyo".to_string(), stats);
+    }
+
+    fn buy_cell_at(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        buyer: ValidAccountId,
+        x: u32,
+        y: u32,
+    ) {
+        buy_prime_cell_at(context, contract, buyer, x, y, 0);
+    }
+
+    fn buy_prime_cell_at(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        buyer: ValidAccountId,
+        x: u32,
+        y: u32,
+        premium: Balance,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(buyer)
+            .block_timestamp(OPEN_TIME)
+            .attached_deposit(LAND_PRICE + MINT_FEE + premium)
+            .build());
+        contract.buy_land_at("tokyo".to_string(), x, y);
+    }
+
+    #[test]
+    fn test_auto_assign_cells() {
+        let (mut context, mut contract) = setup(10);
+        buy_cell_at(&mut context, &mut contract, accounts(2), 1, 0);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 5);
+
+        let map = contract.get_area_map("tokyo".to_string(), U64(0), U64(8));
+        let owned: Vec<(u32, u32)> = map
+            .iter()
+            .filter(|parcel| parcel.token_id.is_some())
+            .map(|parcel| (parcel.x, parcel.y))
+            .collect();
+        assert_eq!(owned, vec![(0, 0), (1, 0), (2, 0), (3, 0), (0, 1), (1, 1)]);
+        assert_eq!(map[6].token_id, None);
+
+        let land = contract.get_land_at("tokyo".to_string(), 1, 0).unwrap();
+        assert_eq!(land.owner_id, accounts(2).to_string());
+        assert_eq!(land.metadata.location.as_deref(), Some("1, 0"));
+        let land = contract.get_land_at("tokyo".to_string(), 0, 1).unwrap();
+        assert_eq!(land.owner_id, accounts(1).to_string());
+        assert_eq!(land.metadata.location.as_deref(), Some("0, 1"));
+        assert!(contract.get_land_at("tokyo".to_string(), 2, 1).is_none());
+    }
+
+    #[test]
+    fn test_area_map_pagination() {
+        let (_, contract) = setup(10);
+        let map = contract.get_area_map("tokyo".to_string(), U64(14), U64(5));
+        assert_eq!(map.len(), 2);
+        assert_eq!((map[0].x, map[0].y), (2, 3));
+        assert_eq!((map[1].x, map[1].y), (3, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cell (1, 0) is already taken")]
+    fn test_buy_land_at_taken_cell() {
+        let (mut context, mut contract) = setup(10);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        buy_cell_at(&mut context, &mut contract, accounts(2), 1, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cell (4, 0) is outside the area grid")]
+    fn test_buy_land_at_outside_grid() {
+        let (mut context, mut contract) = setup(10);
+        buy_cell_at(&mut context, &mut contract, accounts(1), GRID_SIZE, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Area grid has fewer cells than its land limit")]
+    fn test_create_area_grid_too_small() {
+        let (_, mut contract) = setup(1);
+        contract.create_area(
+            "osaka".to_string(),
+            5,
+            2,
+            2,
+            LAND_PRICE.to_string(),
+            OPEN_TIME,
+            CLOSE_TIME,
+            None,
+        );
+    }
+
+    const PREMIUM: Balance = LAND_PRICE * 2;
+
+    fn setup_prime_cells(limit: u64) -> (VMContextBuilder, Contract) {
+        let (context, mut contract) = setup(limit);
+        contract.set_prime_cells(
+            "tokyo".to_string(),
+            vec![(0, 0), (GRID_SIZE - 1, 0)],
+            PREMIUM.to_string(),
+        );
+        (context, contract)
+    }
+
+    #[test]
+    fn test_buy_prime_cell() {
+        let (mut context, mut contract) = setup_prime_cells(10);
+        buy_prime_cell_at(&mut context, &mut contract, accounts(1), 0, 0, PREMIUM);
+
+        let land = contract.get_land_at("tokyo".to_string(), 0, 0).unwrap();
+        assert_eq!(land.owner_id, accounts(1).to_string());
+        let map = contract.get_area_map("tokyo".to_string(), U64(0), U64(4));
+        assert_eq!(map[0].premium, Some(U128(PREMIUM)));
+        assert_eq!(map[1].premium, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Please deposit price equal land price + mint fee")]
+    fn test_buy_prime_cell_without_premium() {
+        let (mut context, mut contract) = setup_prime_cells(10);
+        buy_cell_at(&mut context, &mut contract, accounts(1), GRID_SIZE - 1, 0);
+    }
+
+    #[test]
+    fn test_auto_assign_skips_prime_cells() {
+        let (mut context, mut contract) = setup_prime_cells(10);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 3);
+        assert!(contract.get_land_at("tokyo".to_string(), 0, 0).is_none());
+        assert!(contract.get_land_at("tokyo".to_string(), 3, 0).is_none());
+        assert!(contract.get_land_at("tokyo".to_string(), 0, 1).is_some());
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.remove_prime_cells("tokyo".to_string(), vec![(0, 0)]);
+        assert_eq!(
+            contract.get_prime_cell_premium("tokyo".to_string(), 0, 0),
+            None
+        );
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        let land = contract.get_land_at("tokyo".to_string(), 0, 0).unwrap();
+        assert_eq!(land.metadata.location.as_deref(), Some("0, 0"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cell (0, 0) is already taken")]
+    fn test_buy_prime_cell_conflict() {
+        let (mut context, mut contract) = setup_prime_cells(10);
+        buy_prime_cell_at(&mut context, &mut contract, accounts(1), 0, 0, PREMIUM);
+        buy_prime_cell_at(&mut context, &mut contract, accounts(2), 0, 0, PREMIUM);
+    }
+
+    #[test]
+    fn test_buy_out_area_with_prime_cells() {
+        let (mut context, mut contract) = setup_prime_cells(u64::from(GRID_SIZE * GRID_SIZE) - 2);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 10);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 4);
+        assert_eq!(
+            contract.get_area_phase("tokyo".to_string()).unwrap(),
+            SalePhase::SoldOut
+        );
+        assert!(contract.get_land_at("tokyo".to_string(), 0, 0).is_none());
+        assert!(contract.get_land_at("tokyo".to_string(), 3, 0).is_none());
+    }
+
+    #[test]
+    fn test_sold_prime_cell_frees_a_prime_slot() {
+        let (mut context, mut contract) = setup_prime_cells(u64::from(GRID_SIZE * GRID_SIZE) - 2);
+        buy_prime_cell_at(&mut context, &mut contract, accounts(1), 0, 0, PREMIUM);
+        assert_eq!(
+            contract.get_area("tokyo".to_string()).unwrap().prime_cells,
+            1
+        );
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_prime_cells("tokyo".to_string(), vec![(1, 0)], PREMIUM.to_string());
+        assert_eq!(
+            contract.get_area("tokyo".to_string()).unwrap().prime_cells,
+            2
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Prime cells leave fewer free cells than the lands left to sell")]
+    fn test_set_prime_cells_above_limit() {
+        setup_prime_cells(u64::from(GRID_SIZE * GRID_SIZE) - 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Prime cells leave fewer free cells than the lands left to sell")]
+    fn test_update_area_limit_above_prime_cells() {
+        let (_, mut contract) = setup_prime_cells(10);
+        contract.update_area(
+            "tokyo".to_string(),
+            Some(u64::from(GRID_SIZE * GRID_SIZE) - 1),
+            None,
+            None,
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Premium must be positive")]
+    fn test_set_prime_cells_zero_premium() {
+        let (_, mut contract) = setup(10);
+        contract.set_prime_cells("tokyo".to_string(), vec![(0, 0)], "0".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Cell (0, 0) is already taken")]
+    fn test_set_prime_cells_sold() {
+        let (mut context, mut contract) = setup(10);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_prime_cells("tokyo".to_string(), vec![(0, 0)], PREMIUM.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the area_manager role")]
+    fn test_set_prime_cells_without_role() {
+        let (mut context, mut contract) = setup(10);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.set_prime_cells("tokyo".to_string(), vec![(0, 0)], PREMIUM.to_string());
+    }
+
+    fn treasury(team_share: u32, dao_share: u32, burn_share: u32) -> TreasuryConfig {
+        TreasuryConfig {
+            team_account_id: accounts(3).into(),
+            dao_account_id: accounts(4).into(),
+            team_share,
+            dao_share,
+            burn_share,
+        }
+    }
+
+    #[test]
+    fn test_proceeds_ledger() {
+        let (mut context, mut contract) = setup_prime_cells(10);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 2);
+        buy_prime_cell_at(&mut context, &mut contract, accounts(2), 0, 0, PREMIUM);
+
+        let expected = LAND_PRICE * 3 + PREMIUM;
+        assert_eq!(contract.get_area_proceeds("tokyo".to_string()).0, expected);
+        let proceeds = contract.get_proceeds();
+        assert_eq!(proceeds.collected.0, expected);
+        assert_eq!(proceeds.available.0, expected);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0)
+            .build());
+        contract.set_treasury(treasury(5_000, 3_000, 2_000));
+        assert!(matches!(
+            contract.withdraw_proceeds(),
+            PromiseOrValue::Promise(_)
+        ));
+
+        let proceeds = contract.get_proceeds();
+        assert_eq!(proceeds.collected.0, expected);
+        assert_eq!(proceeds.withdrawn.0, expected / 10 * 8);
+        assert_eq!(proceeds.burned.0, expected / 10 * 2);
+        assert_eq!(proceeds.available.0, 0);
+        // Withdrawals do not touch the per-area ledger.
+        assert_eq!(contract.get_area_proceeds("tokyo".to_string()).0, expected);
+    }
+
+    #[test]
+    fn test_withdraw_proceeds_payout_failed() {
+        let (mut context, mut contract) = setup(10);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 2);
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0)
+            .build());
+        contract.set_treasury(treasury(5_000, 3_000, 2_000));
+        contract.withdraw_proceeds();
+
+        let team_amount = LAND_PRICE * 2 / 10 * 5;
+        let dao_amount = LAND_PRICE * 2 / 10 * 3;
+        testing_env!(
+            context.build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![]), PromiseResult::Failed]
+        );
+        let paid = contract.resolve_withdraw_proceeds(vec![
+            (accounts(3).into(), U128(team_amount)),
+            (accounts(4).into(), U128(dao_amount)),
+        ]);
+        assert_eq!(paid.0, team_amount);
+
+        // The failed DAO payout can be withdrawn again, the burned share stays burned.
+        let proceeds = contract.get_proceeds();
+        assert_eq!(proceeds.withdrawn.0, team_amount);
+        assert_eq!(proceeds.burned.0, LAND_PRICE * 2 / 10 * 2);
+        assert_eq!(proceeds.available.0, dao_amount);
+    }
+
+    #[test]
+    fn test_withdraw_proceeds_burn_only() {
+        let (mut context, mut contract) = setup(10);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0)
+            .build());
+        contract.set_treasury(treasury(0, 0, 10_000));
+        assert!(matches!(
+            contract.withdraw_proceeds(),
+            PromiseOrValue::Value(U128(0))
+        ));
+        assert_eq!(contract.get_proceeds().burned.0, LAND_PRICE);
+        assert!(get_created_receipts().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Treasury shares must add up to 10000")]
+    fn test_set_treasury_invalid_split() {
+        let (_, mut contract) = setup(1);
+        contract.set_treasury(treasury(5_000, 3_000, 1_000));
+    }
+
+    #[test]
+    #[should_panic(expected = "Treasury is not configured")]
+    fn test_withdraw_proceeds_without_treasury() {
+        let (mut context, mut contract) = setup(1);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.withdraw_proceeds();
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller is not owner.")]
+    fn test_withdraw_proceeds_not_owner() {
+        let (mut context, mut contract) = setup(1);
+        contract.set_treasury(treasury(10_000, 0, 0));
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        contract.withdraw_proceeds();
+    }
+
+    #[test]
+    #[should_panic(expected = "Area tokyo already exists")]
+    fn test_create_area_duplicate() {
+        let (_, mut contract) = setup(1);
+        contract.create_area(
+            "tokyo".to_string(),
+            1,
+            GRID_SIZE,
+            GRID_SIZE,
+            LAND_PRICE.to_string(),
+            OPEN_TIME,
+            CLOSE_TIME,
+            None,
+        );
+    }
+
+    #[test]
+    fn test_update_area_keeps_land_sold() {
+        let (mut context, mut contract) = setup(3);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 2);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0)
+            .build());
+        contract.update_area(
+            "tokyo".to_string(),
+            Some(5),
+            Some((LAND_PRICE * 2).to_string()),
+            None,
+            Some(CLOSE_TIME + 1),
+            None,
+        );
+        let area = contract.get_area("tokyo".to_string()).unwrap();
+        assert_eq!(area.land_sold, 2);
+        assert_eq!(area.limit, 5);
+        assert_eq!(area.land_price, LAND_PRICE * 2);
+        assert_eq!(area.close_time, CLOSE_TIME + 1);
+        assert_eq!(area.proceeds, LAND_PRICE * 2);
+
+        // The next land continues the token numbering and the grid.
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(LAND_PRICE * 2 + MINT_FEE)
+            .build());
+        contract.buy_land("tokyo".to_string());
+        let land = contract.get_land("tokyo #2".to_string()).unwrap();
+        assert_eq!(land.location.as_deref(), Some("2, 0"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Area limit cannot be below the 2 lands sold")]
+    fn test_update_area_limit_below_sold() {
+        let (mut context, mut contract) = setup(3);
+        buy_lands_at(&mut context, &mut contract, accounts(1), OPEN_TIME, 2);
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.update_area("tokyo".to_string(), Some(1), None, None, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Presale must open before the public sale")]
+    fn test_update_area_open_before_presale() {
+        let (_, mut contract) = setup_presale(3);
+        contract.update_area(
+            "tokyo".to_string(),
+            None,
+            None,
+            Some(PRESALE_TIME),
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    fn test_pause_and_resume_area() {
+        let (mut context, mut contract) = setup(3);
+        contract.pause_area("tokyo".to_string());
+        assert_eq!(
+            phase_at(&mut context, &contract, OPEN_TIME),
+            SalePhase::Paused
+        );
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.resume_area("tokyo".to_string());
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        assert_eq!(contract.get_area("tokyo".to_string()).unwrap().land_sold, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Lands sales are paused in this area")]
+    fn test_buy_land_paused() {
+        let (mut context, mut contract) = setup(3);
+        contract.pause_area("tokyo".to_string());
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+    }
+
+    #[test]
+    #[should_panic(expected = "This area has ended lands sales")]
+    fn test_buy_land_closed_area() {
+        let (mut context, mut contract) = setup(3);
+        contract.close_area("tokyo".to_string());
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+    }
+
+    #[test]
+    #[should_panic(expected = "Area is closed")]
+    fn test_resume_closed_area() {
+        let (_, mut contract) = setup(3);
+        contract.pause_area("tokyo".to_string());
+        contract.close_area("tokyo".to_string());
+        contract.resume_area("tokyo".to_string());
+    }
+
+    #[test]
+    fn test_delete_area() {
+        let (_, mut contract) = setup(3);
+        contract.delete_area("tokyo".to_string());
+        assert!(contract.get_area("tokyo".to_string()).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot delete an area with lands sold")]
+    fn test_delete_area_with_lands_sold() {
+        let (mut context, mut contract) = setup(3);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.delete_area("tokyo".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot delete an area with prime cells")]
+    fn test_delete_area_with_prime_cells() {
+        let (_, mut contract) = setup_prime_cells(3);
+        contract.delete_area("tokyo".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot delete an area with a presale allowlist")]
+    fn test_delete_area_with_presale_allowlist() {
+        let (_, mut contract) = setup_presale(3);
+        contract.delete_area("tokyo".to_string());
+    }
+
+    #[test]
+    fn test_delete_and_recreate_area() {
+        let (mut context, mut contract) = setup_prime_cells(3);
+        contract.remove_prime_cells("tokyo".to_string(), vec![(0, 0), (GRID_SIZE - 1, 0)]);
+        contract.delete_area("tokyo".to_string());
+        contract.create_area(
+            "tokyo".to_string(),
+            3,
+            GRID_SIZE,
+            GRID_SIZE,
+            LAND_PRICE.to_string(),
+            OPEN_TIME,
+            CLOSE_TIME,
+            None,
+        );
+
+        assert_eq!(
+            contract.get_prime_cell_premium("tokyo".to_string(), 0, 0),
+            None
+        );
+        buy_cell_at(&mut context, &mut contract, accounts(1), 0, 0);
+        let area = contract.get_area("tokyo".to_string()).unwrap();
+        assert_eq!(area.land_sold, 1);
+        assert_eq!(area.prime_cells, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the pauser role")]
+    fn test_pause_area_without_role() {
+        let (mut context, mut contract) = setup(3);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.pause_area("tokyo".to_string());
+    }
+
+    fn land_metadata(title: &str) -> TokenMetadata {
+        TokenMetadata {
+            title: Some(title.to_string()),
+            description: None,
+            media: None,
+            media_hash: None,
+            copies: Some(1),
+            issued_at: None,
+            city: None,
+            location: None,
+            rare: None,
+            mining_efficiency: None,
+            mining_power: None,
+        }
+    }
+
+    #[test]
+    fn test_area_manager_role() {
+        let (mut context, mut contract) = setup(1);
+        contract.grant_role(Role::Admin, accounts(1));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.grant_role(Role::AreaManager, accounts(2));
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"scity_access","version":"1.0.0","event":"role_granted","data":[{"role":"area_manager","account_id":"charlie","sender_id":"bob"}]}"#
+            ]
+        );
+        assert!(contract.has_role(Role::AreaManager, accounts(2)));
+        assert!(contract.has_role(Role::AreaManager, accounts(0)));
+        assert_eq!(
+            contract.get_role_members(Role::AreaManager),
+            vec![accounts(2).to_string()]
+        );
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.update_area("tokyo".to_string(), Some(2), None, None, None, None);
+        assert_eq!(contract.get_area("tokyo".to_string()).unwrap().limit, 2);
+
+        contract.renounce_role(Role::AreaManager);
+        assert!(!contract.has_role(Role::AreaManager, accounts(2)));
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"scity_access","version":"1.0.0","event":"role_renounced","data":[{"role":"area_manager","account_id":"charlie"}]}"#
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the area_manager role")]
+    fn test_revoked_area_manager() {
+        let (mut context, mut contract) = setup(1);
+        contract.grant_role(Role::AreaManager, accounts(2));
+        contract.revoke_role(Role::AreaManager, accounts(2));
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.close_area("tokyo".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can manage admins")]
+    fn test_admin_cannot_grant_admin() {
+        let (mut context, mut contract) = setup(1);
+        contract.grant_role(Role::Admin, accounts(1));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.grant_role(Role::Admin, accounts(2));
+    }
+
+    #[test]
+    fn test_nft_mint_minter() {
+        let (mut context, mut contract) = setup(1);
+        contract.grant_role(Role::Minter, accounts(1));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINT_FEE)
+            .build());
+        contract.nft_mint(
+            Some("reward".to_string()),
+            land_metadata("reward"),
+            None,
+            Some(accounts(2)),
+            0,
+        );
+        assert_eq!(
+            contract.nft_token("reward".to_string()).unwrap().owner_id,
+            accounts(2).to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the minter role")]
+    fn test_nft_mint_without_role() {
+        let (mut context, mut contract) = setup(1);
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(MINT_FEE)
+            .build());
+        contract.nft_mint(None, land_metadata("free land"), None, None, 0);
+    }
+
+    #[test]
+    fn test_ownership_transfer() {
+        let (mut context, mut contract) = setup(1);
+        contract.propose_owner(accounts(1));
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"scity_access","version":"1.0.0","event":"ownership_transfer_proposed","data":[{"old_owner_id":"alice","new_owner_id":"bob"}]}"#
+            ]
+        );
+        assert_eq!(contract.get_pending_owner(), Some(accounts(1).to_string()));
+        // The current owner keeps its privileges until the transfer is accepted.
+        contract.set_treasury(treasury(10_000, 0, 0));
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.accept_ownership();
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"scity_access","version":"1.0.0","event":"ownership_transferred","data":[{"old_owner_id":"alice","new_owner_id":"bob"}]}"#
+            ]
+        );
+        assert_eq!(contract.get_owner(), accounts(1).to_string());
+        assert_eq!(contract.get_pending_owner(), None);
+        contract.set_treasury(treasury(5_000, 5_000, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller is not owner.")]
+    fn test_previous_owner_loses_privileges() {
+        let (mut context, mut contract) = setup(1);
+        contract.propose_owner(accounts(1));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.accept_ownership();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.withdraw_proceeds();
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the area_manager role")]
+    fn test_pending_owner_has_no_privileges() {
+        let (mut context, mut contract) = setup(1);
+        contract.propose_owner(accounts(1));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.close_area("tokyo".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller is not the pending owner")]
+    fn test_cancel_ownership_transfer() {
+        let (mut context, mut contract) = setup(1);
+        contract.propose_owner(accounts(1));
+        contract.cancel_ownership_transfer();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.accept_ownership();
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller is not owner.")]
+    fn test_propose_owner_not_owner() {
+        let (mut context, mut contract) = setup(1);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.propose_owner(accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "The sales feature is paused")]
+    fn test_buy_land_sales_paused() {
+        let (mut context, mut contract) = setup(3);
+        contract.pause(Some(Feature::Sales));
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+    }
+
+    #[test]
+    #[should_panic(expected = "The minting feature is paused")]
+    fn test_nft_mint_paused() {
+        let (mut context, mut contract) = setup(1);
+        contract.pause(Some(Feature::Minting));
+        testing_env!(context.attached_deposit(MINT_FEE).build());
+        contract.nft_mint(None, land_metadata("paused land"), None, None, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The transfers feature is paused")]
+    fn test_nft_transfer_paused() {
+        let (mut context, mut contract) = setup(3);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.pause(Some(Feature::Transfers));
+
+        let token_id = hex::encode(env::sha256(b"tokyo #0"));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(accounts(2), token_id, None, None);
+    }
+
+    #[test]
+    fn test_global_pause() {
+        let (mut context, mut contract) = setup(3);
+        contract.pause(None);
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"scity_access","version":"1.0.0","event":"paused","data":[{"feature":null,"sender_id":"alice"}]}"#
+            ]
+        );
+        assert!(contract.is_paused(Feature::Sales));
+        assert!(contract.is_paused(Feature::Transfers));
+        contract.pause(Some(Feature::Transfers));
+
+        contract.unpause(None);
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"scity_access","version":"1.0.0","event":"unpaused","data":[{"feature":null,"sender_id":"alice"}]}"#
+        );
+        let status = contract.get_pause_status();
+        assert!(!status.all);
+        assert_eq!(status.features, vec![Feature::Transfers]);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+    }
+
+    #[test]
+    #[should_panic(expected = "The sales feature is paused")]
+    fn test_buy_land_globally_paused() {
+        let (mut context, mut contract) = setup(3);
+        contract.pause(None);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the pauser role")]
+    fn test_pause_without_role() {
+        let (mut context, mut contract) = setup(3);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.pause(Some(Feature::Sales));
+    }
+
+    #[test]
+    fn test_update_config() {
+        let (_, mut contract) = setup(3);
+        contract.update_config(None, None, Some(accounts(3)));
+        let updated = contract.get_config();
+        assert_eq!(updated.company_contract_id, accounts(3));
+        assert_eq!(updated.box_contract_id, config().box_contract_id);
+        assert_eq!(updated.land_contract_id, accounts(0));
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"scity_access","version":"1.0.0","event":"config_updated","data":[{"field":"company_contract_id","old":"company.scity.testnet","new":"danny","sender_id":"alice"}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the admin role")]
+    fn test_update_config_without_role() {
+        let (mut context, mut contract) = setup(3);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.update_config(Some(accounts(1)), None, None);
+    }
+
+    /// Buys a land for `accounts(1)` and places `company-1` on it.
+    fn setup_placement() -> (VMContextBuilder, Contract, TokenId) {
+        let (mut context, mut contract) = setup(3);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        let land_id = hex::encode(env::sha256(b"tokyo #0"));
+        place_company(&mut context, &mut contract, accounts(1), &land_id);
+        (context, contract, land_id)
+    }
+
+    /// Places `company-1` with power 10 and efficiency 20, as read from the company contract.
+    fn place_company(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        previous_owner_id: ValidAccountId,
+        land_id: &str,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(config().company_contract_id)
+            .attached_deposit(0)
+            .build());
+        let placed = contract.nft_on_transfer(
+            previous_owner_id.to_string(),
+            previous_owner_id.to_string(),
+            "company-1".to_string(),
+            format!(r#"{{"land_id": "{}"}}"#, land_id),
+        );
+        assert!(matches!(placed, PromiseOrValue::Promise(_)));
+
+        let company = r#"{"token_id": "company-1", "metadata": {"mining_power": 10, "mining_efficiency": 20}}"#;
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Successful(company.as_bytes().to_vec()),
+        );
+        assert!(!contract.resolve_place(land_id.to_string(), "company-1".to_string()));
+    }
+
+    #[test]
+    fn test_place_company() {
+        let (_, contract, land_id) = setup_placement();
+        assert_eq!(
+            contract.get_company_on_land(land_id.clone()),
+            Some("company-1".to_string())
+        );
+        assert_eq!(
+            contract.get_land_of_company("company-1".to_string()),
+            Some(land_id.clone())
+        );
+        let land = contract.nft_token(land_id.clone()).unwrap().metadata;
+        let position = contract.get_mining_position(land_id).unwrap();
+        assert_eq!(position.company_id, "company-1");
+        assert_eq!(
+            position.weight.0,
+            u128::from(land.mining_power.unwrap() + 10)
+                * u128::from(land.mining_efficiency.unwrap() + 20)
+        );
+    }
+
+    #[test]
+    fn test_place_company_without_stats() {
+        let (mut context, mut contract) = setup(3);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        let land_id = hex::encode(env::sha256(b"tokyo #0"));
+        testing_env!(context
+            .predecessor_account_id(config().company_contract_id)
+            .attached_deposit(0)
+            .build());
+        contract.nft_on_transfer(
+            accounts(1).to_string(),
+            accounts(1).to_string(),
+            "company-1".to_string(),
+            format!(r#"{{"land_id": "{}"}}"#, land_id),
+        );
+
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Failed,
+        );
+        assert!(contract.resolve_place(land_id.clone(), "company-1".to_string()));
+        assert_eq!(contract.get_company_on_land(land_id.clone()), None);
+        assert_eq!(contract.get_land_of_company("company-1".to_string()), None);
+        assert_eq!(contract.get_company_owner("company-1".to_string()), None);
+        assert_eq!(contract.get_mining_position(land_id), None);
+    }
+
+    #[test]
+    fn test_place_company_with_max_stats() {
+        let (mut context, mut contract) = setup(3);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        let land_id = hex::encode(env::sha256(b"tokyo #0"));
+        testing_env!(context
+            .predecessor_account_id(config().company_contract_id)
+            .attached_deposit(0)
+            .build());
+        contract.nft_on_transfer(
+            accounts(1).to_string(),
+            accounts(1).to_string(),
+            "company-1".to_string(),
+            format!(r#"{{"land_id": "{}"}}"#, land_id),
+        );
+
+        let company = format!(
+            r#"{{"token_id": "company-1", "metadata": {{"mining_power": {0}, "mining_efficiency": {0}}}}}"#,
+            u64::MAX
+        );
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Successful(company.into_bytes()),
+        );
+        assert!(!contract.resolve_place(land_id.clone(), "company-1".to_string()));
+        let max = Balance::from(u64::MAX);
+        assert_eq!(
+            contract.get_mining_position(land_id).unwrap().weight,
+            U128(max * max)
+        );
+    }
+
+    #[test]
+    fn test_unplace_company_before_resolve_place() {
+        let (mut context, mut contract) = setup(3);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        let land_id = hex::encode(env::sha256(b"tokyo #0"));
+        testing_env!(context
+            .predecessor_account_id(config().company_contract_id)
+            .attached_deposit(0)
+            .build());
+        contract.nft_on_transfer(
+            accounts(1).to_string(),
+            accounts(1).to_string(),
+            "company-1".to_string(),
+            format!(r#"{{"land_id": "{}"}}"#, land_id),
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.unplace_company(land_id.clone());
+        testing_env_with_promise_results(
+            context
+                .predecessor_account_id(accounts(0))
+                .attached_deposit(0)
+                .build(),
+            PromiseResult::Successful(vec![]),
+        );
+        assert!(contract.resolve_unplace(
+            land_id.clone(),
+            "company-1".to_string(),
+            accounts(1).into(),
+            U128(0)
+        ));
+
+        // The stats arrive after the company went back to its owner.
+        let company = r#"{"token_id": "company-1", "metadata": {"mining_power": 10, "mining_efficiency": 20}}"#;
+        testing_env_with_promise_results(
+            context.build(),
+            PromiseResult::Successful(company.as_bytes().to_vec()),
+        );
+        assert!(!contract.resolve_place(land_id.clone(), "company-1".to_string()));
+        assert_eq!(contract.get_company_on_land(land_id.clone()), None);
+        assert_eq!(contract.get_land_of_company("company-1".to_string()), None);
+        assert_eq!(contract.get_mining_position(land_id), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the land owner can place or unplace companies")]
+    fn test_place_company_not_land_owner() {
+        let (mut context, mut contract) = setup(3);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        let land_id = hex::encode(env::sha256(b"tokyo #0"));
+        place_company(&mut context, &mut contract, accounts(2), &land_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only companies can be placed on lands")]
+    fn test_place_other_nft() {
+        let (mut context, mut contract) = setup(3);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.nft_on_transfer(
+            accounts(1).to_string(),
+            accounts(1).to_string(),
+            "nft-1".to_string(),
+            format!(
+                r#"{{"land_id": "{}"}}"#,
+                hex::encode(env::sha256(b"tokyo #0"))
+            ),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "already has a company")]
+    fn test_place_company_land_taken() {
+        let (mut context, mut contract, land_id) = setup_placement();
+        place_company(&mut context, &mut contract, accounts(1), &land_id);
+    }
+
+    #[test]
+    fn test_unplace_company() {
+        let (mut context, mut contract, land_id) = setup_placement();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.unplace_company(land_id.clone());
+        assert_eq!(contract.get_company_on_land(land_id.clone()), None);
+        assert_eq!(contract.get_land_of_company("company-1".to_string()), None);
+
+        // The company is placed back if the company contract fails to return it.
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Failed,
+        );
+        assert!(!contract.resolve_unplace(
+            land_id.clone(),
+            "company-1".to_string(),
+            accounts(1).into(),
+            U128(1)
+        ));
+        assert_eq!(
+            contract.get_company_on_land(land_id),
+            Some("company-1".to_string())
+        );
+        assert_eq!(
+            contract.get_company_owner("company-1".to_string()),
+            Some(accounts(1).to_string())
+        );
+    }
+
+    #[test]
+    fn test_unplace_company_land_taken() {
+        let (mut context, mut contract, land_id) = setup_placement();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.unplace_company(land_id.clone());
+
+        // Another company is placed before the failed return resolves.
+        testing_env!(context
+            .predecessor_account_id(config().company_contract_id)
+            .attached_deposit(0)
+            .build());
+        contract.nft_on_transfer(
+            accounts(1).to_string(),
+            accounts(1).to_string(),
+            "company-2".to_string(),
+            format!(r#"{{"land_id": "{}"}}"#, land_id),
+        );
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Failed,
+        );
+        assert!(!contract.resolve_unplace(
+            land_id.clone(),
+            "company-1".to_string(),
+            accounts(1).into(),
+            U128(1)
+        ));
+        assert_eq!(
+            contract.get_company_on_land(land_id),
+            Some("company-2".to_string())
+        );
+        assert_eq!(
+            contract.get_pending_companies(accounts(1)),
+            vec!["company-1".to_string()]
+        );
+
+        // A claim whose transfer fails again keeps the company claimable.
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.claim_company("company-1".to_string());
+        assert!(contract.get_pending_companies(accounts(1)).is_empty());
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Failed,
+        );
+        assert!(!contract.resolve_claim_company(accounts(1).into(), "company-1".to_string()));
+        assert_eq!(
+            contract.get_pending_companies(accounts(1)),
+            vec!["company-1".to_string()]
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.claim_company("company-1".to_string());
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Successful(vec![]),
+        );
+        assert!(contract.resolve_claim_company(accounts(1).into(), "company-1".to_string()));
+        assert!(contract.get_pending_companies(accounts(1)).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Company company-1 is not waiting to be returned to @charlie")]
+    fn test_claim_company_not_pending() {
+        let (mut context, mut contract, _) = setup_placement();
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        contract.claim_company("company-1".to_string());
+    }
+
+    #[test]
+    fn test_unplace_company_after_land_transfer() {
+        let (mut context, mut contract, land_id) = setup_placement();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(accounts(2), land_id.clone(), None, None);
+        assert_eq!(
+            contract.get_company_owner("company-1".to_string()),
+            Some(accounts(1).to_string())
+        );
+
+        // The new land owner can unplace the company, but it goes back to the account that
+        // placed it.
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.unplace_company(land_id.clone());
+        assert_eq!(contract.get_company_on_land(land_id.clone()), None);
+        assert_eq!(contract.get_company_owner("company-1".to_string()), None);
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                "Unplaced company company-1 of @bob from land {}",
+                land_id
+            )]
+        );
+    }
+
+    #[test]
+    fn test_company_owner_unplaces_after_land_transfer() {
+        let (mut context, mut contract, land_id) = setup_placement();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(accounts(2), land_id.clone(), None, None);
+        contract.unplace_company(land_id.clone());
+        assert_eq!(contract.get_company_on_land(land_id), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The placement feature is paused")]
+    fn test_place_company_paused() {
+        let (mut context, mut contract) = setup(3);
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.pause(None);
+        let land_id = hex::encode(env::sha256(b"tokyo #0"));
+        place_company(&mut context, &mut contract, accounts(1), &land_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The placement feature is paused")]
+    fn test_unplace_company_paused() {
+        let (mut context, mut contract, land_id) = setup_placement();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.pause(Some(Feature::Placement));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.unplace_company(land_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the land owner can place or unplace companies")]
+    fn test_unplace_company_not_land_owner() {
+        let (mut context, mut contract, land_id) = setup_placement();
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        contract.unplace_company(land_id);
+    }
+
+    const RATE: Balance = 1_000;
+    const SECOND: Timestamp = 1_000_000_000;
+
+    fn emission(halving_period: u64) -> EmissionConfig {
+        EmissionConfig {
+            reward_token_id: ValidAccountId::try_from("scity.testnet").unwrap(),
+            rate: U128(RATE),
+            start_time: OPEN_TIME,
+            halving_period,
+        }
+    }
+
+    /// Starts emission, then buys a land for `accounts(1)` and places a company on it at
+    /// `OPEN_TIME`. Returns the land id and the weight of the pair.
+    fn setup_mining(halving_period: u64) -> (VMContextBuilder, Contract, TokenId, Balance) {
+        let (mut context, mut contract) = setup(3);
+        contract.set_emission(emission(halving_period));
+        buy_land_at(&mut context, &mut contract, accounts(1), OPEN_TIME);
+        let land_id = hex::encode(env::sha256(b"tokyo #0"));
+        place_company(&mut context, &mut contract, accounts(1), &land_id);
+        let weight = contract
+            .get_mining_position(land_id.clone())
+            .unwrap()
+            .weight
+            .0;
+        (context, contract, land_id, weight)
+    }
+
+    fn at(context: &mut VMContextBuilder, predecessor: ValidAccountId, now: Timestamp) {
+        testing_env!(context
+            .predecessor_account_id(predecessor)
+            .block_timestamp(now)
+            .attached_deposit(1)
+            .build());
+    }
+
+    #[test]
+    fn test_mining_rewards() {
+        let (mut context, contract, land_id, weight) = setup_mining(0);
+        at(&mut context, accounts(1), OPEN_TIME + 100 * SECOND);
+        assert_eq!(
+            contract.get_land_pending_rewards(land_id).0,
+            weight * RATE * 100
+        );
+        assert_eq!(
+            contract.get_pending_rewards(accounts(1)).0,
+            weight * RATE * 100
+        );
+        assert_eq!(contract.get_pending_rewards(accounts(2)).0, 0);
+        assert_eq!(contract.get_emission_rate().0, RATE);
+    }
+
+    #[test]
+    fn test_mining_rewards_halving() {
+        let (mut context, contract, land_id, weight) = setup_mining(50 * SECOND);
+        at(&mut context, accounts(1), OPEN_TIME + 100 * SECOND);
+        assert_eq!(
+            contract.get_land_pending_rewards(land_id).0,
+            weight * (RATE * 50 + RATE / 2 * 50)
+        );
+        assert_eq!(contract.get_emission_rate().0, RATE / 4);
+    }
+
+    #[test]
+    fn test_set_emission_keeps_mined_rewards() {
+        let (mut context, mut contract, land_id, weight) = setup_mining(0);
+        at(&mut context, accounts(0), OPEN_TIME + 50 * SECOND);
+        let mut faster = emission(0);
+        faster.rate = U128(2 * RATE);
+        contract.set_emission(faster);
+        at(&mut context, accounts(1), OPEN_TIME + 100 * SECOND);
+        assert_eq!(
+            contract.get_land_pending_rewards(land_id).0,
+            weight * (RATE * 50 + 2 * RATE * 50)
+        );
+    }
+
+    #[test]
+    fn test_claim_rewards() {
+        let (mut context, mut contract, land_id, weight) = setup_mining(0);
+        at(&mut context, accounts(1), OPEN_TIME + 100 * SECOND);
+        contract.claim_rewards(vec![land_id.clone()]);
+        assert_eq!(contract.get_land_pending_rewards(land_id.clone()).0, 0);
+
+        // Rewards go back to the land if the payout fails.
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Failed,
+        );
+        let paid = contract.resolve_claim(vec![(land_id.clone(), U128(weight * RATE * 100))]);
+        assert_eq!(paid.0, 0);
+        assert_eq!(
+            contract.get_land_pending_rewards(land_id).0,
+            weight * RATE * 100
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The mining feature is paused")]
+    fn test_claim_rewards_paused() {
+        let (mut context, mut contract, land_id, _) = setup_mining(0);
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.pause(Some(Feature::Mining));
+        at(&mut context, accounts(1), OPEN_TIME + 100 * SECOND);
+        contract.claim_rewards(vec![land_id]);
+    }
+
+    #[test]
+    #[should_panic(expected = "No rewards to claim")]
+    fn test_claim_rewards_nothing_mined() {
+        let (mut context, mut contract, land_id, _) = setup_mining(0);
+        at(&mut context, accounts(1), OPEN_TIME);
+        contract.claim_rewards(vec![land_id]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the land owner can claim its rewards")]
+    fn test_claim_rewards_not_land_owner() {
+        let (mut context, mut contract, land_id, _) = setup_mining(0);
+        at(&mut context, accounts(2), OPEN_TIME + 100 * SECOND);
+        contract.claim_rewards(vec![land_id]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can claim the rewards of between 1 and 20 lands at once")]
+    fn test_claim_rewards_too_many_lands() {
+        let (mut context, mut contract, land_id, _) = setup_mining(0);
+        at(&mut context, accounts(1), OPEN_TIME + 100 * SECOND);
+        contract.claim_rewards(vec![land_id; 21]);
+    }
+
+    #[test]
+    fn test_rejected_staking_returns_land() {
+        let (mut context, mut contract, land_id, weight) = setup_mining(0);
+        let staking = ValidAccountId::try_from("staking.scity.testnet").unwrap();
+        at(&mut context, accounts(1), OPEN_TIME + 100 * SECOND);
+        contract.nft_transfer_call(
+            staking.clone(),
+            land_id.clone(),
+            None,
+            None,
+            r#"{"lock_duration": 5}"#.to_string(),
+        );
+        assert_eq!(contract.get_pending_rewards(accounts(1)).0, 0);
+
+        // The staking contract panicked on the unknown lock.
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Failed,
+        );
+        assert!(!contract.nft_resolve_transfer(
+            accounts(1).into(),
+            staking.into(),
+            HashMap::new(),
+            land_id.clone(),
+        ));
+        assert_eq!(
+            contract.nft_token(land_id.clone()).unwrap().owner_id,
+            accounts(1).to_string()
+        );
+        assert_eq!(
+            contract.get_pending_rewards(accounts(1)).0,
+            weight * RATE * 100
+        );
+        assert!(contract.get_mining_position(land_id).is_some());
+    }
+
+    #[test]
+    fn test_unplace_company_stops_mining() {
+        let (mut context, mut contract, land_id, weight) = setup_mining(0);
+        at(&mut context, accounts(1), OPEN_TIME + 100 * SECOND);
+        contract.unplace_company(land_id.clone());
+        assert_eq!(contract.get_mining_position(land_id.clone()), None);
+
+        at(&mut context, accounts(1), OPEN_TIME + 200 * SECOND);
+        assert_eq!(
+            contract.get_land_pending_rewards(land_id).0,
+            weight * RATE * 100
+        );
+    }
+
+    #[test]
+    fn test_unplace_company_rewards_saturate() {
+        let (mut context, mut contract, land_id, _) = setup_mining(0);
+        let mut position = contract.get_mining_position(land_id.clone()).unwrap();
+        position.weight = U128(Balance::MAX / 2);
+        contract.mining_positions.insert(&land_id, &position);
+        contract
+            .unclaimed_rewards
+            .insert(&land_id, &(Balance::MAX - 1));
+
+        at(&mut context, accounts(1), OPEN_TIME + 100 * SECOND);
+        assert_eq!(
+            contract.get_pending_rewards(accounts(1)),
+            U128(Balance::MAX)
+        );
+        contract.unplace_company(land_id.clone());
+        assert_eq!(contract.get_mining_position(land_id.clone()), None);
+        assert_eq!(contract.get_land_pending_rewards(land_id).0, Balance::MAX);
+    }
+
+    #[test]
+    fn test_admin_configures_contract() {
+        let (mut context, mut contract) = setup(3);
+        contract.grant_role(Role::Admin, accounts(2));
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.set_emission(emission(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller is not owner.")]
+    fn test_set_treasury_admin() {
+        let (mut context, mut contract) = setup(3);
+        contract.grant_role(Role::Admin, accounts(2));
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.set_treasury(treasury(10_000, 0, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Halving period must be 0 or at least one second in nanoseconds")]
+    fn test_set_emission_halving_period_in_seconds() {
+        let (_, mut contract) = setup(3);
+        contract.set_emission(emission(3600));
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller does not have the admin role")]
+    fn test_set_emission_without_role() {
+        let (mut context, mut contract) = setup(3);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.set_emission(emission(0));
+    }
+}
@@ -1,50 +1,130 @@
-use crate::*;
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
-#[serde(crate = "near_sdk::serde")]
-pub struct NFTMetadata {
-    pub spec: String,              // required, essentially a version like "nft-1.0.0"
-    pub name: String,              // required, ex. "Mosaics"
-    pub symbol: String,            // required, ex. "MOSIAC"
-    pub icon: Option<String>,      // Data URL
-    pub base_uri: Option<String>, // Centralized gateway known to have reliable access to decentralized storage assets referenced by `reference` or `media` URLs
-    pub reference: Option<String>, // URL to a JSON file with more info
-    pub reference_hash: Option<Base64VecU8>, // Base64-encoded sha256 hash of JSON from reference field. Required if `reference` is included.
-}
-
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
-#[serde(crate = "near_sdk::serde")]
-pub struct TokenMetadata {
-    pub title: Option<String>,
-    pub description: Option<String>,
-    pub media: Option<String>,
-    pub media_hash: Option<Base64VecU8>,
-    pub copies: Option<u64>,
-    pub issued_at: Option<u64>,
-    pub city: Option<String>,
-    pub location: Option<String>,
-    pub rare: Option<String>,
-    pub mining_efficiency: Option<u32>,
-    pub mining_power: Option<u32>,
-}
-
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
-#[serde(crate = "near_sdk::serde")]
-pub struct AreaMetadata {
-    pub name: String,
-    pub limit: u64,
-    pub land_sold: u64,
-    pub land_price: Balance,
-    pub open_time: Timestamp,
-    pub close_time: Timestamp,
-}
-
-pub trait NonFungibleTokenMetadata {
-    fn nft_metadata(&self) -> NFTMetadata;
-}
-
-#[near_bindgen]
-impl NonFungibleTokenMetadata for Contract {
-    fn nft_metadata(&self) -> NFTMetadata {
-        self.metadata.get().unwrap()
-    }
-}
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub media: Option<String>,
+    pub media_hash: Option<Base64VecU8>,
+    pub copies: Option<u64>,
+    pub issued_at: Option<u64>,
+    pub city: Option<String>,
+    pub location: Option<String>,
+    pub rare: Option<String>,
+    pub mining_efficiency: Option<u32>,
+    pub mining_power: Option<u32>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AreaMetadata {
+    pub name: String,
+    pub limit: u64,
+    /// Size of the area's parcel grid, lands sit on cells `(0..width, 0..height)`.
+    pub width: u32,
+    pub height: u32,
+    /// Row-major index of the first cell that may still be free when auto-assigning cells.
+    pub next_cell: u64,
+    /// Prime cells not sold yet. Auto-assignment skips them.
+    pub prime_cells: u64,
+    pub land_sold: u64,
+    pub land_price: Balance,
+    pub open_time: Timestamp,
+    pub close_time: Timestamp,
+    pub max_per_account: Option<u64>,
+    /// Sales are suspended until the owner resumes the area.
+    pub paused: bool,
+    /// Sales have ended for good.
+    pub closed: bool,
+    pub presale: Option<PresaleMetadata>,
+    pub stats: LandStatsConfig,
+    /// Number of lands minted per rarity tier.
+    pub rarity_minted: HashMap<String, u64>,
+    /// Land sale revenue collected in this area.
+    pub proceeds: Balance,
+}
+
+/// Allowlist-only sale that runs from `open_time` until the area's public `open_time`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PresaleMetadata {
+    pub land_price: Balance,
+    pub limit_per_account: u64,
+    pub open_time: Timestamp,
+    pub allowlist_size: u64,
+}
+
+/// Sale phase of an area, derived from its sale window and remaining supply.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum SalePhase {
+    Upcoming,
+    Presale,
+    Open,
+    Paused,
+    Closed,
+    SoldOut,
+}
+
+impl AreaMetadata {
+    pub(crate) fn assert_valid(&self) {
+        assert!(
+            self.cell_count() >= self.limit,
+            "Area grid has fewer cells than its land limit"
+        );
+        // Every land sold takes a cell, so this keeps a regular free cell for every land left.
+        assert!(
+            self.cell_count() - self.prime_cells >= self.limit,
+            "Prime cells leave fewer free cells than the lands left to sell"
+        );
+        assert!(
+            self.limit >= self.land_sold,
+            "Area limit cannot be below the {} lands sold",
+            self.land_sold
+        );
+        assert!(
+            self.open_time >= MIN_SALE_TIMESTAMP && self.close_time >= MIN_SALE_TIMESTAMP,
+            "Sale times must be in nanoseconds"
+        );
+        assert!(
+            self.open_time < self.close_time,
+            "Open time must be before close time"
+        );
+        if let Some(presale) = &self.presale {
+            assert!(
+                presale.open_time < self.open_time,
+                "Presale must open before the public sale"
+            );
+        }
+    }
+
+    /// Lands are sold from `open_time` (inclusive) until `close_time` (exclusive), both in
+    /// nanoseconds like `env::block_timestamp()`.
+    pub fn phase(&self, now: Timestamp) -> SalePhase {
+        if self.closed {
+            SalePhase::Closed
+        } else if self.paused {
+            SalePhase::Paused
+        } else if self.land_sold >= self.limit {
+            SalePhase::SoldOut
+        } else if now < self.open_time {
+            match &self.presale {
+                Some(presale) if now >= presale.open_time => SalePhase::Presale,
+                _ => SalePhase::Upcoming,
+            }
+        } else if now >= self.close_time {
+            SalePhase::Closed
+        } else {
+            SalePhase::Open
+        }
+    }
+}
+
+#[near_bindgen]
+impl NonFungibleTokenMetadata for Contract {
+    fn nft_metadata(&self) -> NFTMetadata {
+        self.metadata.get().unwrap()
+    }
+}
@@ -0,0 +1,318 @@
+use crate::*;
+use near_sdk::{ext_contract, Gas, PromiseResult};
+
+const GAS_FOR_REWARD_MINT: Gas = 15_000_000_000_000;
+const GAS_FOR_RESOLVE_CLAIM: Gas = 10_000_000_000_000;
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+/// Keeps the settling in `claim_rewards` within its gas budget.
+const MAX_LANDS_PER_CLAIM: usize = 20;
+
+/// How fast mining rewards are emitted.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmissionConfig {
+    /// NEP-141 token rewards are minted in. This contract must be its minter.
+    pub reward_token_id: ValidAccountId,
+    /// Reward per second for each unit of mining weight, before any halving.
+    pub rate: U128,
+    /// Emission starts at this time, in nanoseconds.
+    pub start_time: Timestamp,
+    /// The rate halves every period after `start_time`, in nanoseconds. Never halves if 0,
+    /// otherwise at least one second.
+    pub halving_period: u64,
+}
+
+/// A land mining with the company placed on it. Its weight is the combined mining power of the
+/// pair times their combined mining efficiency.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MiningPosition {
+    pub company_id: TokenId,
+    pub weight: U128,
+    /// Reward index the position was last settled at.
+    pub reward_index: U128,
+}
+
+#[ext_contract(ext_reward_token)]
+trait RewardToken {
+    fn ft_mint(&mut self, account_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+#[ext_contract(ext_mining)]
+trait MiningResolver {
+    fn resolve_claim(&mut self, rewards: Vec<(TokenId, U128)>) -> U128;
+}
+
+impl EmissionConfig {
+    fn rate_at_epoch(&self, epoch: u64) -> Balance {
+        if epoch >= u64::from(Balance::BITS) {
+            0
+        } else {
+            self.rate.0 >> epoch
+        }
+    }
+
+    /// Reward emitted per unit of weight between two times, in seconds.
+    fn emitted(&self, from: u64, to: u64) -> Balance {
+        let start = self.start_time / NANOS_PER_SECOND;
+        let period = self.halving_period / NANOS_PER_SECOND;
+        let mut from = from.max(start);
+        if period == 0 {
+            return self
+                .rate
+                .0
+                .saturating_mul(Balance::from(to.saturating_sub(from)));
+        }
+        let mut emitted = 0;
+        while from < to {
+            let epoch = (from - start) / period;
+            let rate = self.rate_at_epoch(epoch);
+            if rate == 0 {
+                break;
+            }
+            let end = to.min(start + (epoch + 1) * period);
+            emitted = rate
+                .saturating_mul(Balance::from(end - from))
+                .saturating_add(emitted);
+            from = end;
+        }
+        emitted
+    }
+
+    fn current_rate(&self, now: u64) -> Balance {
+        let start = self.start_time / NANOS_PER_SECOND;
+        let period = self.halving_period / NANOS_PER_SECOND;
+        if now < start {
+            return 0;
+        }
+        match (now - start).checked_div(period) {
+            Some(epoch) => self.rate_at_epoch(epoch),
+            None => self.rate.0,
+        }
+    }
+}
+
+impl MiningPosition {
+    /// Rewards mined since the position was last settled, saturating so that settling and
+    /// unplacing never panic.
+    fn mined(&self, reward_index: Balance) -> Balance {
+        self.weight
+            .0
+            .saturating_mul(reward_index.saturating_sub(self.reward_index.0))
+    }
+}
+
+fn now_in_seconds() -> u64 {
+    env::block_timestamp() / NANOS_PER_SECOND
+}
+
+impl Contract {
+    fn current_reward_index(&self) -> Balance {
+        match &self.emission {
+            Some(emission) => self
+                .reward_index
+                .saturating_add(emission.emitted(self.reward_index_updated, now_in_seconds())),
+            None => self.reward_index,
+        }
+    }
+
+    fn internal_update_reward_index(&mut self) {
+        self.reward_index = self.current_reward_index();
+        self.reward_index_updated = now_in_seconds();
+    }
+
+    /// Rewards of the land not claimed yet, including what its position mined so far.
+    fn internal_pending_rewards(&self, land_id: &TokenId) -> Balance {
+        let unclaimed = self.unclaimed_rewards.get(land_id).unwrap_or(0);
+        match self.mining_positions.get(land_id) {
+            Some(position) => unclaimed.saturating_add(position.mined(self.current_reward_index())),
+            None => unclaimed,
+        }
+    }
+
+    /// Moves what the land's position mined so far to its unclaimed rewards.
+    fn internal_settle(&mut self, land_id: &TokenId) {
+        if let Some(mut position) = self.mining_positions.get(land_id) {
+            self.internal_update_reward_index();
+            let mined = position.mined(self.reward_index);
+            if mined > 0 {
+                let unclaimed = self.unclaimed_rewards.get(land_id).unwrap_or(0);
+                self.unclaimed_rewards
+                    .insert(land_id, &unclaimed.saturating_add(mined));
+            }
+            position.reward_index = U128(self.reward_index);
+            self.mining_positions.insert(land_id, &position);
+        }
+    }
+
+    /// Starts mining on a land with the company placed on it and returns the pair's weight,
+    /// or `None` if the land has no metadata to mine with. Stats saturate instead of
+    /// overflowing, since upgrades and fusion can push company stats up to `u64::MAX`.
+    pub(crate) fn internal_start_mining(
+        &mut self,
+        land_id: &TokenId,
+        company_id: &TokenId,
+        company_power: u64,
+        company_efficiency: u64,
+    ) -> Option<Balance> {
+        let land = self.tokens.token_metadata_by_id.get(land_id)?;
+        let power = u64::from(land.mining_power.unwrap_or(0)).saturating_add(company_power);
+        let efficiency =
+            u64::from(land.mining_efficiency.unwrap_or(0)).saturating_add(company_efficiency);
+        let weight = Balance::from(power).saturating_mul(Balance::from(efficiency));
+        self.internal_resume_mining(land_id, company_id, weight);
+        Some(weight)
+    }
+
+    pub(crate) fn internal_resume_mining(
+        &mut self,
+        land_id: &TokenId,
+        company_id: &TokenId,
+        weight: Balance,
+    ) {
+        self.internal_update_reward_index();
+        self.mining_positions.insert(
+            land_id,
+            &MiningPosition {
+                company_id: company_id.clone(),
+                weight: U128(weight),
+                reward_index: U128(self.reward_index),
+            },
+        );
+    }
+
+    /// Stops mining on a land and returns the weight its position had.
+    pub(crate) fn internal_stop_mining(&mut self, land_id: &TokenId) -> Balance {
+        self.internal_settle(land_id);
+        self.mining_positions
+            .remove(land_id)
+            .map(|position| position.weight.0)
+            .unwrap_or(0)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Mints the caller the rewards of `land_ids`, which the caller must own, up to
+    /// `MAX_LANDS_PER_CLAIM` lands at once. The caller must be registered on the reward token.
+    /// Rewards stay with a land when it is transferred, so they go to whoever owns it at claim
+    /// time.
+    pub fn claim_rewards(&mut self, land_ids: Vec<TokenId>) -> Promise {
+        self.pausable.assert_not_paused(Feature::Mining);
+        let emission = self
+            .emission
+            .clone()
+            .expect("Mining emission is not configured");
+        assert!(
+            !land_ids.is_empty() && land_ids.len() <= MAX_LANDS_PER_CLAIM,
+            "Can claim the rewards of between 1 and {} lands at once",
+            MAX_LANDS_PER_CLAIM
+        );
+        let account_id = env::predecessor_account_id();
+
+        let mut rewards = Vec::new();
+        let mut total: Balance = 0;
+        for land_id in land_ids {
+            let land = self
+                .tokens
+                .tokens_by_id
+                .get(&land_id)
+                .expect("Land not found");
+            assert_eq!(
+                land.owner_id, account_id,
+                "Only the land owner can claim its rewards"
+            );
+            self.internal_settle(&land_id);
+            if let Some(amount) = self.unclaimed_rewards.remove(&land_id) {
+                total = total.saturating_add(amount);
+                rewards.push((land_id, U128(amount)));
+            }
+        }
+        assert!(total > 0, "No rewards to claim");
+        env::log(format!("Claimed {} rewards for @{}", total, account_id).as_bytes());
+
+        ext_reward_token::ft_mint(
+            account_id,
+            U128(total),
+            Some(String::from("mining rewards")),
+            emission.reward_token_id.as_ref(),
+            1,
+            GAS_FOR_REWARD_MINT,
+        )
+        .then(ext_mining::resolve_claim(
+            rewards,
+            &env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_CLAIM,
+        ))
+    }
+
+    /// Gives the lands their rewards back if the payout failed, and returns the amount paid.
+    #[private]
+    pub fn resolve_claim(&mut self, rewards: Vec<(TokenId, U128)>) -> U128 {
+        let total = rewards
+            .iter()
+            .map(|(_, amount)| amount.0)
+            .fold(0, Balance::saturating_add);
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return U128(total);
+        }
+        for (land_id, amount) in rewards {
+            let unclaimed = self.unclaimed_rewards.get(&land_id).unwrap_or(0);
+            self.unclaimed_rewards
+                .insert(&land_id, &unclaimed.saturating_add(amount.0));
+        }
+        env::log(format!("Reward payout of {} failed and was restored", total).as_bytes());
+        U128(0)
+    }
+
+    /// Replaces the emission, settling what was mined under the previous one first.
+    pub fn set_emission(&mut self, emission: EmissionConfig) {
+        self.assert_role(Role::Admin);
+        assert!(emission.rate.0 > 0, "Emission rate must be positive");
+        assert!(
+            emission.halving_period == 0 || emission.halving_period >= NANOS_PER_SECOND,
+            "Halving period must be 0 or at least one second in nanoseconds"
+        );
+        self.internal_update_reward_index();
+        self.emission = Some(emission);
+    }
+
+    pub fn get_emission(&self) -> Option<EmissionConfig> {
+        self.emission.clone()
+    }
+
+    /// Reward per second for each unit of mining weight, after halvings.
+    pub fn get_emission_rate(&self) -> U128 {
+        U128(
+            self.emission
+                .as_ref()
+                .map(|emission| emission.current_rate(now_in_seconds()))
+                .unwrap_or(0),
+        )
+    }
+
+    pub fn get_mining_position(&self, land_id: TokenId) -> Option<MiningPosition> {
+        self.mining_positions.get(&land_id)
+    }
+
+    pub fn get_land_pending_rewards(&self, land_id: TokenId) -> U128 {
+        U128(self.internal_pending_rewards(&land_id))
+    }
+
+    /// Rewards the account can claim across all its lands.
+    pub fn get_pending_rewards(&self, account_id: ValidAccountId) -> U128 {
+        let total = self
+            .tokens
+            .tokens_per_owner
+            .get(account_id.as_ref())
+            .map(|lands| {
+                lands
+                    .iter()
+                    .map(|land_id| self.internal_pending_rewards(&land_id))
+                    .fold(0, Balance::saturating_add)
+            })
+            .unwrap_or(0);
+        U128(total)
+    }
+}
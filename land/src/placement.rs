@@ -0,0 +1,334 @@
+use crate::*;
+use near_sdk::serde_json::{self, Value};
+use near_sdk::{ext_contract, Gas, PromiseResult};
+
+const GAS_FOR_COMPANY_TRANSFER: Gas = 15_000_000_000_000;
+const GAS_FOR_COMPANY_VIEW: Gas = 10_000_000_000_000;
+const GAS_FOR_RESOLVE_PLACE: Gas = 15_000_000_000_000;
+const GAS_FOR_RESOLVE_UNPLACE: Gas = 15_000_000_000_000;
+const GAS_FOR_RESOLVE_CLAIM_COMPANY: Gas = 10_000_000_000_000;
+
+/// Message of the company contract's `nft_transfer_call` placing a company on a land.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PlacementMessage {
+    pub land_id: TokenId,
+}
+
+#[ext_contract(ext_company)]
+trait CompanyContract {
+    fn nft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<U64>,
+        memo: Option<String>,
+    );
+
+    fn nft_token(&self, token_id: TokenId) -> Option<Value>;
+}
+
+#[ext_contract(ext_placement)]
+trait PlacementResolver {
+    fn resolve_place(&mut self, land_id: TokenId, company_id: TokenId) -> bool;
+
+    fn resolve_unplace(
+        &mut self,
+        land_id: TokenId,
+        company_id: TokenId,
+        owner_id: AccountId,
+        weight: U128,
+    ) -> bool;
+
+    fn resolve_claim_company(&mut self, owner_id: AccountId, company_id: TokenId) -> bool;
+}
+
+impl Contract {
+    fn assert_land_owner(&self, land_id: &TokenId, account_id: &AccountId) {
+        let land = self
+            .tokens
+            .tokens_by_id
+            .get(land_id)
+            .expect("Land not found");
+        assert_eq!(
+            &land.owner_id, account_id,
+            "Only the land owner can place or unplace companies"
+        );
+    }
+
+    fn internal_add_pending_company(&mut self, owner_id: &AccountId, company_id: &TokenId) {
+        let mut company_ids = self.pending_companies.get(owner_id).unwrap_or_default();
+        company_ids.push(company_id.clone());
+        self.pending_companies.insert(owner_id, &company_ids);
+    }
+
+    fn internal_remove_pending_company(&mut self, owner_id: &AccountId, company_id: &TokenId) {
+        let mut company_ids = self.pending_companies.get(owner_id).unwrap_or_default();
+        let len = company_ids.len();
+        company_ids.retain(|other| other != company_id);
+        assert!(
+            company_ids.len() < len,
+            "Company {} is not waiting to be returned to @{}",
+            company_id,
+            owner_id
+        );
+        if company_ids.is_empty() {
+            self.pending_companies.remove(owner_id);
+        } else {
+            self.pending_companies.insert(owner_id, &company_ids);
+        }
+    }
+
+    fn internal_return_company(
+        &self,
+        owner_id: &AccountId,
+        company_id: &TokenId,
+        memo: &str,
+    ) -> Promise {
+        ext_company::nft_transfer(
+            owner_id.clone(),
+            company_id.clone(),
+            None,
+            Some(String::from(memo)),
+            self.config.company_contract_id.as_ref(),
+            1,
+            GAS_FOR_COMPANY_TRANSFER,
+        )
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Places a company sent with `nft_transfer_call` on the company contract on a land of
+    /// its previous owner, given as `{"land_id": [land_id]}`. The company is held by this
+    /// contract until unplaced and stays on the land if the land is transferred, but it is
+    /// always returned to its previous owner. The pair starts mining once the company's stats
+    /// are read from the company contract.
+    pub fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        assert_eq!(
+            &env::predecessor_account_id(),
+            self.config.company_contract_id.as_ref(),
+            "Only companies can be placed on lands"
+        );
+        self.pausable.assert_not_paused(Feature::Placement);
+        // The sender may be an account approved on the company, but the company is placed for
+        // its previous owner, who must own the land.
+        let PlacementMessage { land_id } =
+            serde_json::from_str(&msg).expect("Invalid placement message");
+        self.assert_land_owner(&land_id, &previous_owner_id);
+        assert!(
+            self.company_by_land.get(&land_id).is_none(),
+            "Land {} already has a company",
+            land_id
+        );
+
+        self.company_by_land.insert(&land_id, &token_id);
+        self.land_by_company.insert(&token_id, &land_id);
+        self.company_owners.insert(&token_id, &previous_owner_id);
+        env::log(
+            format!(
+                "Placed company {} on land {} of @{}, sent by @{}",
+                token_id, land_id, previous_owner_id, sender_id
+            )
+            .as_bytes(),
+        );
+
+        ext_company::nft_token(
+            token_id.clone(),
+            self.config.company_contract_id.as_ref(),
+            0,
+            GAS_FOR_COMPANY_VIEW,
+        )
+        .then(ext_placement::resolve_place(
+            land_id,
+            token_id,
+            &env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_PLACE,
+        ))
+        .into()
+    }
+
+    /// Starts mining with the company's stats, or returns the company if mining cannot start,
+    /// e.g. because its stats could not be read. Does nothing if the company was unplaced in
+    /// the meantime.
+    #[private]
+    pub fn resolve_place(&mut self, land_id: TokenId, company_id: TokenId) -> bool {
+        if self.company_by_land.get(&land_id).as_ref() != Some(&company_id) {
+            env::log(
+                format!(
+                    "Company {} was unplaced from land {} before it started mining",
+                    company_id, land_id
+                )
+                .as_bytes(),
+            );
+            return false;
+        }
+        let token = match env::promise_result(0) {
+            PromiseResult::Successful(value) => serde_json::from_slice::<Option<Value>>(&value)
+                .ok()
+                .flatten(),
+            _ => None,
+        };
+        let stat = |name: &str| {
+            token
+                .as_ref()
+                .and_then(|token| token["metadata"][name].as_u64())
+        };
+        let weight = match (stat("mining_power"), stat("mining_efficiency")) {
+            (Some(power), Some(efficiency)) => {
+                self.internal_start_mining(&land_id, &company_id, power, efficiency)
+            }
+            _ => None,
+        };
+        if weight.is_some() {
+            return false;
+        }
+        self.company_by_land.remove(&land_id);
+        self.land_by_company.remove(&company_id);
+        self.company_owners.remove(&company_id);
+        env::log(
+            format!(
+                "Company {} cannot mine on land {} and was returned",
+                company_id, land_id
+            )
+            .as_bytes(),
+        );
+        true
+    }
+
+    /// Returns the company placed on a land to the account that placed it. Either the land
+    /// owner or that account can unplace it.
+    #[payable]
+    pub fn unplace_company(&mut self, land_id: TokenId) -> Promise {
+        assert_one_yocto();
+        self.pausable.assert_not_paused(Feature::Placement);
+        let company_id = self
+            .company_by_land
+            .get(&land_id)
+            .expect("No company is placed on this land");
+        let owner_id = self
+            .company_owners
+            .get(&company_id)
+            .expect("Company owner not found");
+        let caller_id = env::predecessor_account_id();
+        if caller_id != owner_id {
+            self.assert_land_owner(&land_id, &caller_id);
+        }
+        self.company_by_land.remove(&land_id);
+        self.land_by_company.remove(&company_id);
+        self.company_owners.remove(&company_id);
+        let weight = self.internal_stop_mining(&land_id);
+        env::log(
+            format!(
+                "Unplaced company {} of @{} from land {}",
+                company_id, owner_id, land_id
+            )
+            .as_bytes(),
+        );
+
+        self.internal_return_company(&owner_id, &company_id, "unplace")
+            .then(ext_placement::resolve_unplace(
+                land_id,
+                company_id,
+                owner_id,
+                U128(weight),
+                &env::current_account_id(),
+                0,
+                GAS_FOR_RESOLVE_UNPLACE,
+            ))
+    }
+
+    /// Puts the company back on its land, mining again, if it could not be returned to
+    /// `owner_id`, the account that placed it. If another company was placed there in the
+    /// meantime, the company waits for that account to claim it with `claim_company`.
+    #[private]
+    pub fn resolve_unplace(
+        &mut self,
+        land_id: TokenId,
+        company_id: TokenId,
+        owner_id: AccountId,
+        weight: U128,
+    ) -> bool {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return true;
+        }
+        if self.company_by_land.get(&land_id).is_none() {
+            self.company_by_land.insert(&land_id, &company_id);
+            self.land_by_company.insert(&company_id, &land_id);
+            self.company_owners.insert(&company_id, &owner_id);
+            self.internal_resume_mining(&land_id, &company_id, weight.0);
+            env::log(format!("Placed company {} back on land {}", company_id, land_id).as_bytes());
+        } else {
+            self.internal_add_pending_company(&owner_id, &company_id);
+            env::log(
+                format!(
+                    "Company {} could not be returned and land {} is taken, @{} can claim it",
+                    company_id, land_id, owner_id
+                )
+                .as_bytes(),
+            );
+        }
+        false
+    }
+
+    /// Retries returning a company to the account that placed it, after its return failed.
+    #[payable]
+    pub fn claim_company(&mut self, company_id: TokenId) -> Promise {
+        assert_one_yocto();
+        self.pausable.assert_not_paused(Feature::Placement);
+        let owner_id = env::predecessor_account_id();
+        self.internal_remove_pending_company(&owner_id, &company_id);
+        self.internal_return_company(&owner_id, &company_id, "claim")
+            .then(ext_placement::resolve_claim_company(
+                owner_id,
+                company_id,
+                &env::current_account_id(),
+                0,
+                GAS_FOR_RESOLVE_CLAIM_COMPANY,
+            ))
+    }
+
+    /// Keeps the company claimable if it could not be returned again.
+    #[private]
+    pub fn resolve_claim_company(&mut self, owner_id: AccountId, company_id: TokenId) -> bool {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return true;
+        }
+        self.internal_add_pending_company(&owner_id, &company_id);
+        env::log(
+            format!(
+                "Company {} could not be returned to @{}",
+                company_id, owner_id
+            )
+            .as_bytes(),
+        );
+        false
+    }
+
+    pub fn get_company_on_land(&self, land_id: TokenId) -> Option<TokenId> {
+        self.company_by_land.get(&land_id)
+    }
+
+    pub fn get_land_of_company(&self, company_id: TokenId) -> Option<TokenId> {
+        self.land_by_company.get(&company_id)
+    }
+
+    /// Account that placed the company and gets it back when it is unplaced.
+    pub fn get_company_owner(&self, company_id: TokenId) -> Option<AccountId> {
+        self.company_owners.get(&company_id)
+    }
+
+    /// Companies waiting to be claimed by the account after their return failed.
+    pub fn get_pending_companies(&self, account_id: ValidAccountId) -> Vec<TokenId> {
+        self.pending_companies
+            .get(account_id.as_ref())
+            .unwrap_or_default()
+    }
+}
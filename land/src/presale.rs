@@ -0,0 +1,116 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Configures an allowlist-only presale that runs from `open_time` until the area opens to
+    /// the public. Calling it again updates the price, cap and window but keeps the allowlist.
+    pub fn set_area_presale(
+        &mut self,
+        name: String,
+        price: String,
+        limit_per_account: u64,
+        open_time: Timestamp,
+    ) {
+        self.assert_role(Role::AreaManager);
+        let (area_hash, mut area) = self.internal_get_area(&name);
+        assert!(
+            open_time >= MIN_SALE_TIMESTAMP,
+            "Sale times must be in nanoseconds"
+        );
+        assert!(
+            open_time < area.open_time,
+            "Presale must open before the public sale"
+        );
+        assert!(
+            limit_per_account > 0,
+            "Presale limit per account must be positive"
+        );
+
+        let allowlist_size = area
+            .presale
+            .map(|presale| presale.allowlist_size)
+            .unwrap_or(0);
+        area.presale = Some(PresaleMetadata {
+            land_price: price.parse().unwrap(),
+            limit_per_account,
+            open_time,
+            allowlist_size,
+        });
+        self.area_metadata_by_id.insert(&area_hash, &area);
+    }
+
+    pub fn add_to_presale_allowlist(&mut self, name: String, account_ids: Vec<ValidAccountId>) {
+        self.assert_role(Role::AreaManager);
+        let (area_hash, mut area) = self.internal_get_area(&name);
+        let presale = area.presale.as_mut().expect("Area has no presale");
+
+        for account_id in account_ids {
+            if self
+                .presale_allowlist
+                .insert(&(area_hash.clone(), account_id.into()))
+            {
+                presale.allowlist_size += 1;
+            }
+        }
+        self.area_metadata_by_id.insert(&area_hash, &area);
+    }
+
+    pub fn remove_from_presale_allowlist(
+        &mut self,
+        name: String,
+        account_ids: Vec<ValidAccountId>,
+    ) {
+        self.assert_role(Role::AreaManager);
+        let (area_hash, mut area) = self.internal_get_area(&name);
+        let presale = area.presale.as_mut().expect("Area has no presale");
+
+        for account_id in account_ids {
+            if self
+                .presale_allowlist
+                .remove(&(area_hash.clone(), account_id.into()))
+            {
+                presale.allowlist_size -= 1;
+            }
+        }
+        self.area_metadata_by_id.insert(&area_hash, &area);
+    }
+
+    pub fn is_on_presale_allowlist(&self, name: String, account_id: ValidAccountId) -> bool {
+        self.presale_allowlist
+            .contains(&(hash_area_name(&name), account_id.into()))
+    }
+
+    pub fn get_presale_purchases(&self, name: String, account_id: ValidAccountId) -> u64 {
+        self.presale_purchases
+            .get(&(hash_area_name(&name), account_id.into()))
+            .unwrap_or(0)
+    }
+}
+
+impl Contract {
+    /// Checks the buyer against the area's presale allowlist and cap, records `count` purchases
+    /// and returns the presale price of one land.
+    pub(crate) fn internal_record_presale_purchase(
+        &mut self,
+        area: &AreaMetadata,
+        area_hash: &str,
+        buyer_id: &AccountId,
+        count: u64,
+    ) -> Balance {
+        let presale = area.presale.as_ref().expect("Area has no presale");
+        let key = (area_hash.to_string(), buyer_id.clone());
+        assert!(
+            self.presale_allowlist.contains(&key),
+            "Account is not on the presale allowlist"
+        );
+
+        let purchases = self.presale_purchases.get(&key).unwrap_or(0) + count;
+        assert!(
+            purchases <= presale.limit_per_account,
+            "Presale limit per account reached"
+        );
+        self.presale_purchases.insert(&key, &purchases);
+
+        presale.land_price
+    }
+}
@@ -0,0 +1,121 @@
+use crate::*;
+
+/// How the stats of lands minted in an area are rolled.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LandStatsConfig {
+    pub mining_efficiency: StatRange,
+    pub mining_power: StatRange,
+    pub rarity_table: Vec<RarityTier>,
+}
+
+impl Default for LandStatsConfig {
+    fn default() -> Self {
+        Self {
+            mining_efficiency: StatRange { min: 80, max: 179 },
+            mining_power: StatRange { min: 33, max: 61 },
+            rarity_table: vec![RarityTier {
+                rare: String::from("R"),
+                weight: 1,
+                multiplier: MULTIPLIER_BASE,
+                supply: None,
+            }],
+        }
+    }
+}
+
+/// Stats rolled for a single land.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LandStats {
+    pub rare: String,
+    pub mining_efficiency: u32,
+    pub mining_power: u32,
+}
+
+/// Supply of one rarity tier in an area, as returned by `get_area_rarity_supply`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RaritySupply {
+    pub rare: String,
+    pub weight: u32,
+    pub minted: u64,
+    pub remaining: Option<u64>,
+}
+
+fn roll_stat(range: &StatRange, value: u64, multiplier: u32) -> u32 {
+    apply_multiplier(range.roll(value), multiplier).min(u64::from(u32::MAX)) as u32
+}
+
+fn is_available(tier: &RarityTier, minted: &HashMap<String, u64>) -> bool {
+    match tier.supply {
+        Some(supply) => minted.get(&tier.rare).copied().unwrap_or(0) < supply,
+        None => true,
+    }
+}
+
+impl LandStatsConfig {
+    fn assert_valid(&self) {
+        self.mining_efficiency.assert_valid();
+        self.mining_power.assert_valid();
+        assert_valid_rarity_table(&self.rarity_table);
+    }
+
+    /// Picks a tier by weight, skipping tiers whose supply is exhausted.
+    fn roll_rarity(&self, value: u64, minted: &HashMap<String, u64>) -> &RarityTier {
+        let available: Vec<&RarityTier> = self
+            .rarity_table
+            .iter()
+            .filter(|tier| tier.weight > 0 && is_available(tier, minted))
+            .collect();
+        assert!(!available.is_empty(), "All rarity tiers are sold out");
+        pick_weighted::<&RarityTier>(&available, |tier| tier.weight, value)
+    }
+
+    /// Rolls the stats of `token_id` with [`random_u64`], given the lands `minted` per tier.
+    pub fn roll(&self, seed: &[u8], token_id: &str, minted: &HashMap<String, u64>) -> LandStats {
+        let tier = self.roll_rarity(random_u64(seed, token_id, "rare"), minted);
+        LandStats {
+            rare: tier.rare.clone(),
+            mining_efficiency: roll_stat(
+                &self.mining_efficiency,
+                random_u64(seed, token_id, "mining_efficiency"),
+                tier.multiplier,
+            ),
+            mining_power: roll_stat(
+                &self.mining_power,
+                random_u64(seed, token_id, "mining_power"),
+                tier.multiplier,
+            ),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the stat ranges and rarity table used for lands minted in the area from now on.
+    /// Counts of lands already minted per tier are kept.
+    pub fn set_area_stats(&mut self, name: String, stats: LandStatsConfig) {
+        self.assert_role(Role::AreaManager);
+        stats.assert_valid();
+        let (area_hash, mut area) = self.internal_get_area(&name);
+        area.stats = stats;
+        self.area_metadata_by_id.insert(&area_hash, &area);
+    }
+
+    pub fn get_area_rarity_supply(&self, name: String) -> Vec<RaritySupply> {
+        let (_, area) = self.internal_get_area(&name);
+        area.stats
+            .rarity_table
+            .iter()
+            .map(|tier| {
+                let minted = area.rarity_minted.get(&tier.rare).copied().unwrap_or(0);
+                RaritySupply {
+                    rare: tier.rare.clone(),
+                    weight: tier.weight,
+                    minted,
+                    remaining: tier.supply.map(|supply| supply.saturating_sub(minted)),
+                }
+            })
+            .collect()
+    }
+}
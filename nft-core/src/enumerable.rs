@@ -0,0 +1,84 @@
+use crate::*;
+
+pub trait NonFungibleTokenEnumeration<M> {
+    fn nft_tokens(&self, from_index: U64, limit: U64) -> Vec<JsonToken<M>>;
+
+    fn nft_tokens_batch(&self, token_ids: Vec<String>) -> Vec<JsonToken<M>>;
+
+    fn nft_supply_for_owner(&self, account_id: AccountId) -> U64;
+
+    fn nft_tokens_for_owner(
+        &self,
+        account_id: AccountId,
+        from_index: U64,
+        limit: U64,
+    ) -> Vec<JsonToken<M>>;
+}
+
+impl<M> NonFungibleTokenEnumeration<M> for NonFungibleToken<M>
+where
+    M: BorshSerialize + BorshDeserialize,
+{
+    fn nft_tokens(&self, from_index: U64, limit: U64) -> Vec<JsonToken<M>> {
+        let keys = self.token_metadata_by_id.keys_as_vector();
+        let start = u64::from(from_index);
+        let end = min(start + u64::from(limit), keys.len());
+        (start..end)
+            .map(|i| self.nft_token(keys.get(i).unwrap()).unwrap())
+            .collect()
+    }
+
+    fn nft_tokens_batch(&self, token_ids: Vec<String>) -> Vec<JsonToken<M>> {
+        token_ids
+            .into_iter()
+            .map(|token_id| self.nft_token(token_id).unwrap())
+            .collect()
+    }
+
+    fn nft_supply_for_owner(&self, account_id: AccountId) -> U64 {
+        let tokens_owner = self.tokens_per_owner.get(&account_id);
+        if let Some(tokens_owner) = tokens_owner {
+            U64(tokens_owner.len())
+        } else {
+            U64(0)
+        }
+    }
+
+    fn nft_tokens_for_owner(
+        &self,
+        account_id: AccountId,
+        from_index: U64,
+        limit: U64,
+    ) -> Vec<JsonToken<M>> {
+        let tokens_owner = self.tokens_per_owner.get(&account_id);
+        let tokens = if let Some(tokens_owner) = tokens_owner {
+            tokens_owner
+        } else {
+            return vec![];
+        };
+        let keys = tokens.as_vector();
+        let start = u64::from(from_index);
+        let end = min(start + u64::from(limit), keys.len());
+        (start..end)
+            .map(|i| self.nft_token(keys.get(i).unwrap()).unwrap())
+            .collect()
+    }
+}
+
+impl<M> NonFungibleToken<M>
+where
+    M: BorshSerialize + BorshDeserialize,
+{
+    /// Metadata of every token held by `owner_id`, used by the `get_*_by_owner` views.
+    pub fn token_metadata_for_owner(&self, owner_id: &AccountId) -> Vec<M> {
+        self.tokens_per_owner
+            .get(owner_id)
+            .map(|token_ids| {
+                token_ids
+                    .iter()
+                    .map(|token_id| self.token_metadata_by_id.get(&token_id).unwrap())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,562 @@
+//! NFT core shared by the Scity land and company contracts.
+//!
+//! [`NonFungibleToken`] owns the token collections and implements transfer, approval, resolve
+//! and enumeration logic once, generic over the per-collection token metadata. Contracts embed
+//! it as a field and expose its methods with the `impl_non_fungible_token_*` macros. The stat
+//! ranges, rarity tiers and random rolls both collections mint with live here too.
+use std::cmp::min;
+use std::collections::HashMap;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::json_types::{Base64VecU8, ValidAccountId, U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, AccountId, Balance, CryptoHash, IntoStorageKey, Promise, StorageUsage};
+
+pub use crate::enumerable::*;
+pub use crate::internal::*;
+pub use crate::metadata::*;
+pub use crate::nft_core::*;
+pub use crate::payout::*;
+pub use crate::stats::*;
+pub use crate::token::*;
+
+mod enumerable;
+mod internal;
+mod macros;
+mod metadata;
+mod nft_core;
+mod payout;
+mod stats;
+mod token;
+
+/// Helper structure to for keys of the persistent collections, nested under the prefix the
+/// contract passes to [`NonFungibleToken::new`].
+#[derive(BorshSerialize)]
+pub(crate) enum StorageKey {
+    TokensPerOwner,
+    TokenPerOwnerInner { account_id_hash: CryptoHash },
+    TokensById,
+    TokenMetadataById,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct NonFungibleToken<M> {
+    pub tokens_per_owner: LookupMap<AccountId, UnorderedSet<TokenId>>,
+
+    pub tokens_by_id: LookupMap<TokenId, Token>,
+
+    pub token_metadata_by_id: UnorderedMap<TokenId, M>,
+
+    /// The storage size in bytes for one account.
+    pub extra_storage_in_bytes_per_token: StorageUsage,
+
+    prefix: Vec<u8>,
+}
+
+impl<M> NonFungibleToken<M>
+where
+    M: BorshSerialize + BorshDeserialize,
+{
+    pub fn new<P>(prefix: P, owner_id: &AccountId) -> Self
+    where
+        P: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        let mut this = Self {
+            tokens_per_owner: LookupMap::new(storage_key(&prefix, StorageKey::TokensPerOwner)),
+            tokens_by_id: LookupMap::new(storage_key(&prefix, StorageKey::TokensById)),
+            token_metadata_by_id: UnorderedMap::new(storage_key(
+                &prefix,
+                StorageKey::TokenMetadataById,
+            )),
+            extra_storage_in_bytes_per_token: 0,
+            prefix,
+        };
+
+        this.measure_min_token_storage_cost(owner_id);
+
+        this
+    }
+
+    fn measure_min_token_storage_cost(&mut self, owner_id: &AccountId) {
+        let initial_storage_usage = env::storage_usage();
+        let tmp_account_id = "a".repeat(64);
+        let u = self.new_tokens_set(&tmp_account_id);
+        self.tokens_per_owner.insert(&tmp_account_id, &u);
+
+        let tokens_per_owner_entry_in_bytes = env::storage_usage() - initial_storage_usage;
+        let owner_id_extra_cost_in_bytes = (tmp_account_id.len() - owner_id.len()) as u64;
+
+        self.extra_storage_in_bytes_per_token =
+            tokens_per_owner_entry_in_bytes + owner_id_extra_cost_in_bytes;
+
+        self.tokens_per_owner.remove(&tmp_account_id);
+    }
+
+    pub(crate) fn new_tokens_set(&self, account_id: &AccountId) -> UnorderedSet<TokenId> {
+        UnorderedSet::new(storage_key(
+            &self.prefix,
+            StorageKey::TokenPerOwnerInner {
+                account_id_hash: hash_account_id(account_id),
+            },
+        ))
+    }
+}
+
+fn storage_key(prefix: &[u8], key: StorageKey) -> Vec<u8> {
+    [prefix.to_vec(), key.try_to_vec().unwrap()].concat()
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{
+        accounts, get_created_receipts, testing_env_with_promise_results, VMContextBuilder,
+    };
+    use near_sdk::{testing_env, MockedBlockchain, PromiseResult};
+
+    use super::*;
+
+    #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(crate = "near_sdk::serde")]
+    struct TestMetadata {
+        title: String,
+    }
+
+    fn get_context(predecessor_account_id: ValidAccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id);
+        builder
+    }
+
+    fn setup() -> (VMContextBuilder, NonFungibleToken<TestMetadata>) {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut tokens = NonFungibleToken::new(b"t".to_vec(), accounts(0).as_ref());
+        for (token_id, owner) in [("1", accounts(1)), ("2", accounts(1)), ("3", accounts(2))] {
+            tokens.internal_mint(
+                token_id.to_string(),
+                owner.into(),
+                &TestMetadata {
+                    title: format!("Token #{}", token_id),
+                },
+                HashMap::new(),
+            );
+        }
+        (context, tokens)
+    }
+
+    fn owner_of(tokens: &NonFungibleToken<TestMetadata>, token_id: &str) -> AccountId {
+        tokens.nft_token(token_id.to_string()).unwrap().owner_id
+    }
+
+    #[test]
+    fn test_new() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let tokens = NonFungibleToken::<TestMetadata>::new(b"t".to_vec(), accounts(0).as_ref());
+        assert!(tokens.extra_storage_in_bytes_per_token > 0);
+        assert_eq!(tokens.nft_total_supply().0, 0);
+    }
+
+    #[test]
+    fn test_mint() {
+        let (_, tokens) = setup();
+        assert_eq!(tokens.nft_total_supply().0, 3);
+        let token = tokens.nft_token("1".to_string()).unwrap();
+        assert_eq!(token.owner_id, accounts(1).to_string());
+        assert_eq!(token.metadata.title, "Token #1");
+        assert!(tokens.nft_token("4".to_string()).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Token already exists")]
+    fn test_mint_existing_token() {
+        let (_, mut tokens) = setup();
+        tokens.internal_mint(
+            "1".to_string(),
+            accounts(3).into(),
+            &TestMetadata {
+                title: "Copy".to_string(),
+            },
+            HashMap::new(),
+        );
+    }
+
+    #[test]
+    fn test_burn() {
+        let (_, mut tokens) = setup();
+        let token = tokens.internal_burn(&"1".to_string());
+        assert_eq!(token.owner_id, accounts(1).to_string());
+        assert!(tokens.nft_token("1".to_string()).is_none());
+        assert_eq!(tokens.nft_total_supply().0, 2);
+        assert_eq!(tokens.nft_supply_for_owner(accounts(1).into()).0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Token not found")]
+    fn test_burn_missing_token() {
+        let (_, mut tokens) = setup();
+        tokens.internal_burn(&"4".to_string());
+    }
+
+    #[test]
+    fn test_transfer() {
+        let (mut context, mut tokens) = setup();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        tokens.nft_transfer(accounts(3), "1".to_string(), None, None);
+
+        assert_eq!(owner_of(&tokens, "1"), accounts(3).to_string());
+        assert_eq!(tokens.nft_supply_for_owner(accounts(1).into()).0, 1);
+        assert_eq!(tokens.nft_supply_for_owner(accounts(3).into()).0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_transfer_without_deposit() {
+        let (mut context, mut tokens) = setup();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        tokens.nft_transfer(accounts(3), "1".to_string(), None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn test_transfer_unauthorized() {
+        let (mut context, mut tokens) = setup();
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        tokens.nft_transfer(accounts(3), "1".to_string(), None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The token owner and the receiver should be different")]
+    fn test_transfer_to_owner() {
+        let (mut context, mut tokens) = setup();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        tokens.nft_transfer(accounts(1), "1".to_string(), None, None);
+    }
+
+    #[test]
+    fn test_approve_and_transfer() {
+        let (mut context, mut tokens) = setup();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(env::storage_byte_cost() * 100)
+            .build());
+        tokens.nft_approve("1".to_string(), accounts(3), None);
+        let token = tokens.nft_token("1".to_string()).unwrap();
+        assert_eq!(
+            token.approved_account_ids.get(accounts(3).as_ref()),
+            Some(&U64(0))
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(1)
+            .build());
+        tokens.nft_transfer(accounts(4), "1".to_string(), Some(U64(0)), None);
+
+        let token = tokens.nft_token("1".to_string()).unwrap();
+        assert_eq!(token.owner_id, accounts(4).to_string());
+        assert!(token.approved_account_ids.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "The actual approval_id 0 is different from the given approval_id 1")]
+    fn test_transfer_with_stale_approval_id() {
+        let (mut context, mut tokens) = setup();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(env::storage_byte_cost() * 100)
+            .build());
+        tokens.nft_approve("1".to_string(), accounts(3), None);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(1)
+            .build());
+        tokens.nft_transfer(accounts(4), "1".to_string(), Some(U64(1)), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Predecessor must be the token owner.")]
+    fn test_approve_by_non_owner() {
+        let (mut context, mut tokens) = setup();
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(env::storage_byte_cost() * 100)
+            .build());
+        tokens.nft_approve("1".to_string(), accounts(3), None);
+    }
+
+    #[test]
+    fn test_revoke() {
+        let (mut context, mut tokens) = setup();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(env::storage_byte_cost() * 100)
+            .build());
+        tokens.nft_approve("1".to_string(), accounts(3), None);
+        tokens.nft_approve("1".to_string(), accounts(4), None);
+
+        testing_env!(context.attached_deposit(1).build());
+        tokens.nft_revoke("1".to_string(), accounts(3));
+        let approved = tokens
+            .nft_token("1".to_string())
+            .unwrap()
+            .approved_account_ids;
+        assert!(!approved.contains_key(accounts(3).as_ref()));
+        assert_eq!(approved.get(accounts(4).as_ref()), Some(&U64(1)));
+
+        tokens.nft_revoke_all("1".to_string());
+        let approved = tokens
+            .nft_token("1".to_string())
+            .unwrap()
+            .approved_account_ids;
+        assert!(approved.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_transfer_kept_by_receiver() {
+        let (mut context, mut tokens) = setup();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        tokens.nft_transfer_call(accounts(3), "1".to_string(), None, None, "".to_string());
+        assert_eq!(owner_of(&tokens, "1"), accounts(3).to_string());
+
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Successful(b"false".to_vec()),
+        );
+        assert!(tokens.nft_resolve_transfer(
+            accounts(1).into(),
+            accounts(3).into(),
+            HashMap::new(),
+            "1".to_string(),
+        ));
+        assert_eq!(owner_of(&tokens, "1"), accounts(3).to_string());
+    }
+
+    #[test]
+    fn test_resolve_transfer_returned_by_receiver() {
+        let (mut context, mut tokens) = setup();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        tokens.nft_transfer_call(accounts(3), "1".to_string(), None, None, "".to_string());
+
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Successful(b"true".to_vec()),
+        );
+        let mut approved_account_ids = HashMap::new();
+        approved_account_ids.insert(accounts(4).to_string(), U64(0));
+        assert!(!tokens.nft_resolve_transfer(
+            accounts(1).into(),
+            accounts(3).into(),
+            approved_account_ids,
+            "1".to_string(),
+        ));
+
+        let token = tokens.nft_token("1".to_string()).unwrap();
+        assert_eq!(token.owner_id, accounts(1).to_string());
+        assert_eq!(
+            token.approved_account_ids.get(accounts(4).as_ref()),
+            Some(&U64(0))
+        );
+        assert_eq!(tokens.nft_supply_for_owner(accounts(1).into()).0, 2);
+        assert_eq!(tokens.nft_supply_for_owner(accounts(3).into()).0, 0);
+    }
+
+    #[test]
+    fn test_resolve_transfer_receiver_failed() {
+        let (mut context, mut tokens) = setup();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        tokens.nft_transfer_call(accounts(3), "1".to_string(), None, None, "".to_string());
+
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Failed,
+        );
+        assert!(!tokens.nft_resolve_transfer(
+            accounts(1).into(),
+            accounts(3).into(),
+            HashMap::new(),
+            "1".to_string(),
+        ));
+        assert_eq!(owner_of(&tokens, "1"), accounts(1).to_string());
+    }
+
+    #[test]
+    fn test_resolve_transfer_after_receiver_moved_token() {
+        let (mut context, mut tokens) = setup();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        tokens.nft_transfer_call(accounts(3), "1".to_string(), None, None, "".to_string());
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        tokens.nft_transfer(accounts(4), "1".to_string(), None, None);
+
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Successful(b"true".to_vec()),
+        );
+        assert!(tokens.nft_resolve_transfer(
+            accounts(1).into(),
+            accounts(3).into(),
+            HashMap::new(),
+            "1".to_string(),
+        ));
+        assert_eq!(owner_of(&tokens, "1"), accounts(4).to_string());
+    }
+
+    #[test]
+    fn test_resolve_transfer_unexpected_result() {
+        let (mut context, mut tokens) = setup();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        tokens.nft_transfer_call(accounts(3), "1".to_string(), None, None, "".to_string());
+
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Successful(b"\"staked\"".to_vec()),
+        );
+        assert!(!tokens.nft_resolve_transfer(
+            accounts(1).into(),
+            accounts(3).into(),
+            HashMap::new(),
+            "1".to_string(),
+        ));
+        assert_eq!(owner_of(&tokens, "1"), accounts(1).to_string());
+    }
+
+    #[test]
+    fn test_resolve_transfer_refunds_approvals() {
+        let (mut context, mut tokens) = setup();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        tokens.nft_transfer_call(accounts(3), "1".to_string(), None, None, "".to_string());
+
+        // Kept without approvals, nothing to refund.
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Successful(b"false".to_vec()),
+        );
+        tokens.nft_resolve_transfer(
+            accounts(1).into(),
+            accounts(3).into(),
+            HashMap::new(),
+            "1".to_string(),
+        );
+        assert!(get_created_receipts().is_empty());
+
+        // Returned, the approvals the receiver added are refunded to it.
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(env::storage_byte_cost() * 100)
+            .build());
+        tokens.nft_approve("1".to_string(), accounts(4), None);
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Successful(b"true".to_vec()),
+        );
+        tokens.nft_resolve_transfer(
+            accounts(1).into(),
+            accounts(3).into(),
+            HashMap::new(),
+            "1".to_string(),
+        );
+        assert_eq!(get_created_receipts().len(), 1);
+        let token = tokens.nft_token("1".to_string()).unwrap();
+        assert_eq!(token.owner_id, accounts(1).to_string());
+        assert!(token.approved_account_ids.is_empty());
+    }
+
+    #[test]
+    fn test_transfer_payout() {
+        let (mut context, mut tokens) = setup();
+        let mut royalty = HashMap::new();
+        royalty.insert(accounts(4).to_string(), 1_000);
+        tokens.internal_mint(
+            "4".to_string(),
+            accounts(1).into(),
+            &TestMetadata {
+                title: "Token #4".to_string(),
+            },
+            royalty,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        let payout = tokens
+            .nft_transfer_payout(
+                accounts(3),
+                "4".to_string(),
+                None,
+                None,
+                Some(U128(1_000)),
+                Some(1),
+            )
+            .unwrap();
+        assert_eq!(payout.get(accounts(4).as_ref()), Some(&U128(100)));
+        assert_eq!(payout.get(accounts(1).as_ref()), Some(&U128(900)));
+        assert_eq!(owner_of(&tokens, "4"), accounts(3).to_string());
+    }
+
+    #[test]
+    fn test_enumeration() {
+        let (_, tokens) = setup();
+        let page: Vec<TokenId> = tokens
+            .nft_tokens(U64(1), U64(10))
+            .into_iter()
+            .map(|token| token.token_id)
+            .collect();
+        assert_eq!(page, vec!["2".to_string(), "3".to_string()]);
+
+        let batch = tokens.nft_tokens_batch(vec!["3".to_string(), "1".to_string()]);
+        assert_eq!(batch[0].metadata.title, "Token #3");
+        assert_eq!(batch[1].metadata.title, "Token #1");
+
+        assert_eq!(tokens.nft_supply_for_owner(accounts(1).into()).0, 2);
+        assert_eq!(tokens.nft_supply_for_owner(accounts(3).into()).0, 0);
+        assert_eq!(
+            tokens
+                .nft_tokens_for_owner(accounts(1).into(), U64(1), U64(1))
+                .len(),
+            1
+        );
+        assert!(tokens
+            .nft_tokens_for_owner(accounts(3).into(), U64(0), U64(10))
+            .is_empty());
+        assert_eq!(
+            tokens.token_metadata_for_owner(accounts(2).as_ref()),
+            vec![TestMetadata {
+                title: "Token #3".to_string()
+            }]
+        );
+    }
+}
@@ -0,0 +1,211 @@
+/// Exposes the core transfer, approval and resolve methods of the contract's
+/// [`NonFungibleToken`](crate::NonFungibleToken) field.
+///
+/// An optional `$before_transfer` method of the contract runs before every transfer, e.g. to
+/// check that transfers are not paused.
+///
+/// The contract module must have `NonFungibleTokenCore` and `NonFungibleTokenResolver` in scope.
+#[macro_export]
+macro_rules! impl_non_fungible_token_core {
+    ($contract: ident, $tokens: ident, $metadata: ty $(, $before_transfer: ident)?) => {
+        #[near_sdk::near_bindgen]
+        impl $crate::NonFungibleTokenCore<$metadata> for $contract {
+            #[payable]
+            fn nft_transfer(
+                &mut self,
+                receiver_id: near_sdk::json_types::ValidAccountId,
+                token_id: $crate::TokenId,
+                approval_id: Option<near_sdk::json_types::U64>,
+                memo: Option<String>,
+            ) {
+                $(self.$before_transfer();)?
+                $crate::NonFungibleTokenCore::<$metadata>::nft_transfer(
+                    &mut self.$tokens,
+                    receiver_id,
+                    token_id,
+                    approval_id,
+                    memo,
+                )
+            }
+
+            #[payable]
+            fn nft_transfer_call(
+                &mut self,
+                receiver_id: near_sdk::json_types::ValidAccountId,
+                token_id: $crate::TokenId,
+                approval_id: Option<near_sdk::json_types::U64>,
+                memo: Option<String>,
+                msg: String,
+            ) -> near_sdk::Promise {
+                $(self.$before_transfer();)?
+                $crate::NonFungibleTokenCore::<$metadata>::nft_transfer_call(
+                    &mut self.$tokens,
+                    receiver_id,
+                    token_id,
+                    approval_id,
+                    memo,
+                    msg,
+                )
+            }
+
+            #[payable]
+            fn nft_approve(
+                &mut self,
+                token_id: $crate::TokenId,
+                account_id: near_sdk::json_types::ValidAccountId,
+                msg: Option<String>,
+            ) {
+                $crate::NonFungibleTokenCore::<$metadata>::nft_approve(
+                    &mut self.$tokens,
+                    token_id,
+                    account_id,
+                    msg,
+                )
+            }
+
+            #[payable]
+            fn nft_revoke(
+                &mut self,
+                token_id: $crate::TokenId,
+                account_id: near_sdk::json_types::ValidAccountId,
+            ) {
+                $crate::NonFungibleTokenCore::<$metadata>::nft_revoke(
+                    &mut self.$tokens,
+                    token_id,
+                    account_id,
+                )
+            }
+
+            #[payable]
+            fn nft_revoke_all(&mut self, token_id: $crate::TokenId) {
+                $crate::NonFungibleTokenCore::<$metadata>::nft_revoke_all(
+                    &mut self.$tokens,
+                    token_id,
+                )
+            }
+
+            fn nft_total_supply(&self) -> near_sdk::json_types::U64 {
+                $crate::NonFungibleTokenCore::<$metadata>::nft_total_supply(&self.$tokens)
+            }
+
+            fn nft_token(&self, token_id: $crate::TokenId) -> Option<$crate::JsonToken<$metadata>> {
+                $crate::NonFungibleTokenCore::<$metadata>::nft_token(&self.$tokens, token_id)
+            }
+        }
+
+        #[near_sdk::near_bindgen]
+        impl $crate::NonFungibleTokenResolver for $contract {
+            #[private]
+            fn nft_resolve_transfer(
+                &mut self,
+                owner_id: near_sdk::AccountId,
+                receiver_id: near_sdk::AccountId,
+                approved_account_ids: std::collections::HashMap<
+                    near_sdk::AccountId,
+                    near_sdk::json_types::U64,
+                >,
+                token_id: $crate::TokenId,
+            ) -> bool {
+                $crate::NonFungibleTokenResolver::nft_resolve_transfer(
+                    &mut self.$tokens,
+                    owner_id,
+                    receiver_id,
+                    approved_account_ids,
+                    token_id,
+                )
+            }
+        }
+    };
+}
+
+/// Exposes `nft_transfer_payout` for marketplaces that respect royalties, running the optional
+/// `$before_transfer` method first like [`impl_non_fungible_token_core!`].
+///
+/// The contract module must have `NonFungibleTokenPayout` in scope.
+#[macro_export]
+macro_rules! impl_non_fungible_token_payout {
+    ($contract: ident, $tokens: ident $(, $before_transfer: ident)?) => {
+        #[near_sdk::near_bindgen]
+        impl $crate::NonFungibleTokenPayout for $contract {
+            #[payable]
+            fn nft_transfer_payout(
+                &mut self,
+                receiver_id: near_sdk::json_types::ValidAccountId,
+                token_id: $crate::TokenId,
+                approval_id: Option<near_sdk::json_types::U64>,
+                memo: Option<String>,
+                balance: Option<near_sdk::json_types::U128>,
+                max_len_payout: Option<u32>,
+            ) -> Option<$crate::Payout> {
+                $(self.$before_transfer();)?
+                $crate::NonFungibleTokenPayout::nft_transfer_payout(
+                    &mut self.$tokens,
+                    receiver_id,
+                    token_id,
+                    approval_id,
+                    memo,
+                    balance,
+                    max_len_payout,
+                )
+            }
+        }
+    };
+}
+
+/// Exposes the enumeration views of the contract's [`NonFungibleToken`](crate::NonFungibleToken)
+/// field.
+///
+/// The contract module must have `NonFungibleTokenEnumeration` in scope.
+#[macro_export]
+macro_rules! impl_non_fungible_token_enumeration {
+    ($contract: ident, $tokens: ident, $metadata: ty) => {
+        #[near_sdk::near_bindgen]
+        impl $crate::NonFungibleTokenEnumeration<$metadata> for $contract {
+            fn nft_tokens(
+                &self,
+                from_index: near_sdk::json_types::U64,
+                limit: near_sdk::json_types::U64,
+            ) -> Vec<$crate::JsonToken<$metadata>> {
+                $crate::NonFungibleTokenEnumeration::<$metadata>::nft_tokens(
+                    &self.$tokens,
+                    from_index,
+                    limit,
+                )
+            }
+
+            fn nft_tokens_batch(
+                &self,
+                token_ids: Vec<String>,
+            ) -> Vec<$crate::JsonToken<$metadata>> {
+                $crate::NonFungibleTokenEnumeration::<$metadata>::nft_tokens_batch(
+                    &self.$tokens,
+                    token_ids,
+                )
+            }
+
+            fn nft_supply_for_owner(
+                &self,
+                account_id: near_sdk::AccountId,
+            ) -> near_sdk::json_types::U64 {
+                $crate::NonFungibleTokenEnumeration::<$metadata>::nft_supply_for_owner(
+                    &self.$tokens,
+                    account_id,
+                )
+            }
+
+            fn nft_tokens_for_owner(
+                &self,
+                account_id: near_sdk::AccountId,
+                from_index: near_sdk::json_types::U64,
+                limit: near_sdk::json_types::U64,
+            ) -> Vec<$crate::JsonToken<$metadata>> {
+                $crate::NonFungibleTokenEnumeration::<$metadata>::nft_tokens_for_owner(
+                    &self.$tokens,
+                    account_id,
+                    from_index,
+                    limit,
+                )
+            }
+        }
+    };
+}
@@ -0,0 +1,79 @@
+use crate::*;
+
+pub fn royalty_to_payout(a: u32, b: Balance) -> U128 {
+    U128(a as u128 * b / 10_000u128)
+}
+
+pub trait NonFungibleTokenPayout {
+    fn nft_transfer_payout(
+        &mut self,
+        receiver_id: ValidAccountId,
+        token_id: TokenId,
+        approval_id: Option<U64>,
+        memo: Option<String>,
+        balance: Option<U128>,
+        max_len_payout: Option<u32>,
+    ) -> Option<Payout>;
+}
+
+impl<M> NonFungibleTokenPayout for NonFungibleToken<M>
+where
+    M: BorshSerialize + BorshDeserialize,
+{
+    // CUSTOM - this method is included for marketplaces that respect royalties
+    fn nft_transfer_payout(
+        &mut self,
+        receiver_id: ValidAccountId,
+        token_id: TokenId,
+        approval_id: Option<U64>,
+        memo: Option<String>,
+        balance: Option<U128>,
+        max_len_payout: Option<u32>,
+    ) -> Option<Payout> {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        let previous_token = self.internal_transfer(
+            &sender_id,
+            receiver_id.as_ref(),
+            &token_id,
+            approval_id,
+            memo,
+        );
+        refund_approved_account_ids(
+            previous_token.owner_id.clone(),
+            &previous_token.approved_account_ids,
+        );
+
+        // compute payouts based on balance option
+        // adds in contract_royalty and computes previous owner royalty from remainder
+        let owner_id = previous_token.owner_id;
+        let mut total_perpetual = 0;
+        balance.map(|balance| {
+            let balance_u128 = u128::from(balance);
+            let mut payout: Payout = HashMap::new();
+            let royalty = self.tokens_by_id.get(&token_id).expect("No token").royalty;
+
+            if let Some(max_len_payout) = max_len_payout {
+                assert!(
+                    royalty.len() as u32 <= max_len_payout,
+                    "Market cannot payout to that many receivers"
+                );
+            }
+
+            for (k, v) in royalty.iter() {
+                let key = k.clone();
+                if key != owner_id {
+                    payout.insert(key, royalty_to_payout(*v, balance_u128));
+                    total_perpetual += *v;
+                }
+            }
+            // payout to previous owner
+            payout.insert(
+                owner_id,
+                royalty_to_payout(10000 - total_perpetual, balance_u128),
+            );
+
+            payout
+        })
+    }
+}
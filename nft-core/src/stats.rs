@@ -0,0 +1,87 @@
+use crate::*;
+
+/// Stat multipliers are expressed in basis points, `10_000` leaves a stat unchanged.
+pub const MULTIPLIER_BASE: u32 = 10_000;
+
+/// Inclusive range a mining stat is drawn from.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StatRange {
+    pub min: u64,
+    pub max: u64,
+}
+
+/// A rarity tier of a drop table, e.g. C, R, SR or SSR.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RarityTier {
+    pub rare: String,
+    /// Relative chance of rolling this tier among the tiers that can still be rolled.
+    pub weight: u32,
+    /// Applied to the mining stats, in basis points of [`MULTIPLIER_BASE`].
+    pub multiplier: u32,
+    /// Maximum number of tokens of this tier, unlimited if `None`. Only land areas cap tiers.
+    #[serde(default)]
+    pub supply: Option<u64>,
+}
+
+impl StatRange {
+    pub fn assert_valid(&self) {
+        assert!(self.min <= self.max, "Stat range min must not exceed max");
+    }
+
+    /// Draws a stat from the range, `value` being a random number.
+    pub fn roll(&self, value: u64) -> u64 {
+        match (self.max - self.min).checked_add(1) {
+            Some(span) => self.min + value % span,
+            None => value,
+        }
+    }
+}
+
+/// Scales `stat` by `multiplier`, in basis points of [`MULTIPLIER_BASE`], capped at `u64::MAX`.
+pub fn apply_multiplier(stat: u64, multiplier: u32) -> u64 {
+    let scaled = u128::from(stat) * u128::from(multiplier) / u128::from(MULTIPLIER_BASE);
+    scaled.min(u128::from(u64::MAX)) as u64
+}
+
+/// Panics unless the table has a positive weight, positive multipliers and unique tiers.
+pub fn assert_valid_rarity_table(rarity_table: &[RarityTier]) {
+    assert!(
+        rarity_table.iter().any(|tier| tier.weight > 0),
+        "Rarity table must have a positive weight"
+    );
+    for (i, tier) in rarity_table.iter().enumerate() {
+        assert!(tier.multiplier > 0, "Rarity multiplier must be positive");
+        assert!(
+            rarity_table[..i]
+                .iter()
+                .all(|other| other.rare != tier.rare),
+            "Duplicate rarity tier {}",
+            tier.rare
+        );
+    }
+}
+
+/// Picks an item by weight, `value` being a random number. The items must have a positive
+/// total weight.
+pub fn pick_weighted<T>(items: &[T], weight: impl Fn(&T) -> u32, value: u64) -> &T {
+    let total_weight: u64 = items.iter().map(|item| u64::from(weight(item))).sum();
+    let mut roll = value % total_weight;
+    for item in items {
+        if roll < u64::from(weight(item)) {
+            return item;
+        }
+        roll -= u64::from(weight(item));
+    }
+    unreachable!()
+}
+
+/// Every roll uses its own hash of the block random seed and token id, so tokens minted in the
+/// same block still differ.
+pub fn random_u64(seed: &[u8], token_id: &str, stat: &str) -> u64 {
+    let hash = env::sha256(&[seed, token_id.as_bytes(), stat.as_bytes()].concat());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    u64::from_le_bytes(bytes)
+}
@@ -1,25 +1,25 @@
-use crate::*;
-
-pub type TokenId = String;
-pub type Payout = HashMap<AccountId, U128>;
-
-#[derive(BorshDeserialize, BorshSerialize)]
-pub struct Token {
-    pub owner_id: AccountId,
-    pub approved_account_ids: HashMap<AccountId, U64>,
-    pub next_approval_id: u64,
-    // CUSTOM - fields
-    pub royalty: HashMap<AccountId, u32>,
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(crate = "near_sdk::serde")]
-pub struct JsonToken {
-    pub token_id: TokenId,
-    pub owner_id: AccountId,
-    pub metadata: TokenMetadata,
-    pub approved_account_ids: HashMap<AccountId, U64>,
-
-    // CUSTOM - fields
-    pub royalty: HashMap<AccountId, u32>,
-}
+use crate::*;
+
+pub type TokenId = String;
+pub type Payout = HashMap<AccountId, U128>;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Token {
+    pub owner_id: AccountId,
+    pub approved_account_ids: HashMap<AccountId, U64>,
+    pub next_approval_id: u64,
+    // CUSTOM - fields
+    pub royalty: HashMap<AccountId, u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonToken<M> {
+    pub token_id: TokenId,
+    pub owner_id: AccountId,
+    pub metadata: M,
+    pub approved_account_ids: HashMap<AccountId, U64>,
+
+    // CUSTOM - fields
+    pub royalty: HashMap<AccountId, u32>,
+}
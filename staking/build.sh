@@ -0,0 +1,5 @@
+#!/bin/bash
+set -e
+cd "`dirname $0`"
+RUSTFLAGS='-C link-arg=-s' cargo build -p scity-staking --target wasm32-unknown-unknown --release
+cp ../target/wasm32-unknown-unknown/release/scity_staking.wasm ./res/staking.wasm
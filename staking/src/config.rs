@@ -0,0 +1,87 @@
+use crate::*;
+
+/// Basis points multipliers are expressed in, `10_000` being x1.
+pub const MULTIPLIER_BASE: u32 = 10_000;
+
+/// A lock lands can be staked with. Longer locks usually earn a bigger multiplier.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LockOption {
+    /// In nanoseconds.
+    pub duration: u64,
+    /// Applied to the reward rate, in basis points of [`MULTIPLIER_BASE`].
+    pub multiplier: u32,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakingConfig {
+    /// Reward per second for a land staked without a lock.
+    pub reward_rate: U128,
+    pub lock_options: Vec<LockOption>,
+    /// Share of a locked land's rewards forfeited when it is unstaked before its lock ends, in
+    /// basis points of [`MULTIPLIER_BASE`].
+    pub early_unstake_penalty: u32,
+}
+
+impl StakingConfig {
+    pub(crate) fn assert_valid(&self) {
+        assert!(self.reward_rate.0 > 0, "Reward rate must be positive");
+        assert!(
+            self.early_unstake_penalty <= MULTIPLIER_BASE,
+            "Early unstake penalty cannot exceed {}",
+            MULTIPLIER_BASE
+        );
+        for (i, option) in self.lock_options.iter().enumerate() {
+            assert!(option.duration > 0, "Lock duration must be positive");
+            assert!(
+                option.multiplier >= MULTIPLIER_BASE,
+                "Lock multiplier must be at least {}",
+                MULTIPLIER_BASE
+            );
+            assert!(
+                self.lock_options[..i]
+                    .iter()
+                    .all(|other| other.duration != option.duration),
+                "Duplicate lock duration {}",
+                option.duration
+            );
+        }
+    }
+
+    /// Multiplier of a lock duration, x1 without a lock.
+    pub(crate) fn multiplier_for(&self, duration: u64) -> u32 {
+        if duration == 0 {
+            return MULTIPLIER_BASE;
+        }
+        self.lock_options
+            .iter()
+            .find(|option| option.duration == duration)
+            .map(|option| option.multiplier)
+            .unwrap_or_else(|| env::panic(format!("No lock option of {}", duration).as_bytes()))
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Replaces the staking config, settling what was earned under the previous rate first.
+    /// Lands already staked keep their lock and multiplier.
+    pub fn set_staking_config(&mut self, config: StakingConfig) {
+        self.assert_owner();
+        config.assert_valid();
+        self.internal_update_reward_index();
+        self.config = config;
+    }
+
+    pub fn get_staking_config(&self) -> StakingConfig {
+        self.config.clone()
+    }
+
+    pub fn get_land_contract_id(&self) -> AccountId {
+        self.land_contract_id.clone()
+    }
+
+    pub fn get_reward_token_id(&self) -> AccountId {
+        self.reward_token_id.clone()
+    }
+}
@@ -0,0 +1,57 @@
+use crate::*;
+
+pub(crate) fn hash_account_id(account_id: &AccountId) -> CryptoHash {
+    let mut hash = CryptoHash::default();
+    hash.copy_from_slice(&env::sha256(account_id.as_bytes()));
+    hash
+}
+
+pub(crate) fn assert_one_yocto() {
+    assert_eq!(
+        env::attached_deposit(),
+        1,
+        "Requires attached deposit of exactly 1 yoctoNEAR",
+    )
+}
+
+impl Contract {
+    pub(crate) fn assert_owner(&self) {
+        assert!(
+            env::predecessor_account_id() == self.owner_id,
+            "Caller is not owner."
+        );
+    }
+
+    pub(crate) fn internal_add_position_to_owner(
+        &mut self,
+        account_id: &AccountId,
+        land_id: &TokenId,
+    ) {
+        let mut land_ids = self.positions_per_owner.get(account_id).unwrap_or_else(|| {
+            UnorderedSet::new(
+                StorageKey::PositionsPerOwnerInner {
+                    account_id_hash: hash_account_id(account_id),
+                }
+                .try_to_vec()
+                .unwrap(),
+            )
+        });
+        land_ids.insert(land_id);
+        self.positions_per_owner.insert(account_id, &land_ids);
+    }
+
+    pub(crate) fn internal_remove_position_from_owner(
+        &mut self,
+        account_id: &AccountId,
+        land_id: &TokenId,
+    ) {
+        if let Some(mut land_ids) = self.positions_per_owner.get(account_id) {
+            land_ids.remove(land_id);
+            if land_ids.is_empty() {
+                self.positions_per_owner.remove(account_id);
+            } else {
+                self.positions_per_owner.insert(account_id, &land_ids);
+            }
+        }
+    }
+}
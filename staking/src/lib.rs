@@ -0,0 +1,581 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, UnorderedSet};
+use near_sdk::json_types::{ValidAccountId, U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{
+    env, near_bindgen, AccountId, Balance, CryptoHash, PanicOnDefault, Promise, PromiseOrValue,
+    Timestamp,
+};
+
+pub use crate::config::*;
+use crate::internal::*;
+pub use crate::rewards::*;
+pub use crate::stake::*;
+pub use scity_access::*;
+
+mod config;
+mod internal;
+mod rewards;
+mod stake;
+
+pub type TokenId = String;
+
+near_sdk::setup_alloc!();
+
+/// Stakes lands of the land contract for rewards in a NEP-141 token, paid from a pool the owner
+/// funds.
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    pub owner_id: AccountId,
+
+    /// Account proposed as the next owner, until it accepts.
+    pub pending_owner_id: Option<AccountId>,
+
+    pub access: AccessControl,
+
+    pub pausable: Pausable,
+
+    /// Only lands of this contract can be staked.
+    pub land_contract_id: AccountId,
+
+    /// NEP-141 token rewards are paid in.
+    pub reward_token_id: AccountId,
+
+    pub config: StakingConfig,
+
+    pub positions: LookupMap<TokenId, StakePosition>,
+
+    /// Lands staked by each account.
+    pub positions_per_owner: LookupMap<AccountId, UnorderedSet<TokenId>>,
+
+    /// Rewards of unstaked lands and failed payouts of each account, not claimed yet.
+    pub unclaimed_rewards: LookupMap<AccountId, Balance>,
+
+    /// Reward tokens held for payouts.
+    pub reward_pool: Balance,
+
+    /// Reward a land staked without a lock earned from the start, as of
+    /// `reward_index_updated`.
+    pub reward_index: Balance,
+
+    /// In seconds.
+    pub reward_index_updated: u64,
+
+    pub total_staked: u64,
+}
+
+/// Helper structure to for keys of the persistent collections.
+#[derive(BorshSerialize)]
+pub enum StorageKey {
+    AccessControl,
+    Positions,
+    PositionsPerOwner,
+    PositionsPerOwnerInner { account_id_hash: CryptoHash },
+    UnclaimedRewards,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(
+        owner_id: ValidAccountId,
+        land_contract_id: ValidAccountId,
+        reward_token_id: ValidAccountId,
+        config: StakingConfig,
+    ) -> Self {
+        assert!(!env::state_exists(), "Already initialized");
+        config.assert_valid();
+        Self {
+            owner_id: owner_id.into(),
+            pending_owner_id: None,
+            access: AccessControl::new(StorageKey::AccessControl.try_to_vec().unwrap()),
+            pausable: Pausable::default(),
+            land_contract_id: land_contract_id.into(),
+            reward_token_id: reward_token_id.into(),
+            config,
+            positions: LookupMap::new(StorageKey::Positions.try_to_vec().unwrap()),
+            positions_per_owner: LookupMap::new(
+                StorageKey::PositionsPerOwner.try_to_vec().unwrap(),
+            ),
+            unclaimed_rewards: LookupMap::new(StorageKey::UnclaimedRewards.try_to_vec().unwrap()),
+            reward_pool: 0,
+            reward_index: 0,
+            reward_index_updated: now_in_seconds(),
+            total_staked: 0,
+        }
+    }
+}
+
+scity_access::impl_access_control!(Contract, access, owner_id);
+scity_access::impl_ownership!(Contract, owner_id, pending_owner_id);
+scity_access::impl_pausable!(Contract, pausable);
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::convert::TryFrom;
+
+    use near_sdk::test_utils::{
+        accounts, get_logs, testing_env_with_promise_results, VMContextBuilder,
+    };
+    use near_sdk::{testing_env, MockedBlockchain, PromiseResult};
+
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+    const START: u64 = 1_000 * SECOND;
+    const LOCK: u64 = 100 * SECOND;
+    const RATE: Balance = 1000;
+
+    fn get_context(predecessor_account_id: ValidAccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id)
+            .block_timestamp(START);
+        builder
+    }
+
+    fn land_contract() -> ValidAccountId {
+        ValidAccountId::try_from("land.scity.testnet").unwrap()
+    }
+
+    fn reward_token() -> ValidAccountId {
+        ValidAccountId::try_from("scity.testnet").unwrap()
+    }
+
+    fn staking_config() -> StakingConfig {
+        StakingConfig {
+            reward_rate: U128(RATE),
+            lock_options: vec![LockOption {
+                duration: LOCK,
+                multiplier: 20_000,
+            }],
+            early_unstake_penalty: 5_000,
+        }
+    }
+
+    /// Sets the caller and the time, in nanoseconds.
+    fn at(context: &mut VMContextBuilder, predecessor: ValidAccountId, now: u64) {
+        testing_env!(context
+            .predecessor_account_id(predecessor)
+            .block_timestamp(now)
+            .attached_deposit(1)
+            .build());
+    }
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let contract = Contract::new(
+            accounts(0),
+            land_contract(),
+            reward_token(),
+            staking_config(),
+        );
+        (context, contract)
+    }
+
+    fn stake(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        owner_id: ValidAccountId,
+        land_id: &str,
+        msg: &str,
+    ) {
+        at(context, land_contract(), START);
+        match contract.nft_on_transfer(
+            owner_id.clone().into(),
+            owner_id.into(),
+            land_id.to_string(),
+            msg.to_string(),
+        ) {
+            PromiseOrValue::Value(returned) => assert!(!returned),
+            PromiseOrValue::Promise(_) => panic!("Expected a value"),
+        }
+    }
+
+    fn fund(context: &mut VMContextBuilder, contract: &mut Contract, amount: Balance) {
+        testing_env!(context.predecessor_account_id(reward_token()).build());
+        contract.ft_on_transfer(accounts(0), U128(amount), "".to_string());
+    }
+
+    fn lands(land_ids: &[&str]) -> Vec<TokenId> {
+        land_ids.iter().map(|land_id| land_id.to_string()).collect()
+    }
+
+    fn locked_msg() -> String {
+        format!(r#"{{"lock_duration": {}}}"#, LOCK)
+    }
+
+    #[test]
+    fn test_new() {
+        let (_, contract) = setup();
+        assert_eq!(contract.get_staking_config(), staking_config());
+        assert_eq!(contract.get_land_contract_id(), land_contract().to_string());
+        assert_eq!(contract.get_reward_token_id(), reward_token().to_string());
+        assert_eq!(contract.get_total_staked().0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Lock multiplier must be at least 10000")]
+    fn test_new_lock_multiplier_below_base() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut config = staking_config();
+        config.lock_options[0].multiplier = 5_000;
+        Contract::new(accounts(0), land_contract(), reward_token(), config);
+    }
+
+    #[test]
+    fn test_stake_without_lock() {
+        let (mut context, mut contract) = setup();
+        stake(&mut context, &mut contract, accounts(1), "1", "");
+        let position = contract.get_position("1".to_string()).unwrap();
+        assert_eq!(position.owner_id, accounts(1).to_string());
+        assert_eq!(position.multiplier, MULTIPLIER_BASE);
+        assert_eq!(position.unlock_at, START);
+        assert_eq!(contract.get_positions(accounts(1), None, None).len(), 1);
+        assert_eq!(contract.get_total_staked().0, 1);
+    }
+
+    #[test]
+    fn test_stake_with_lock() {
+        let (mut context, mut contract) = setup();
+        stake(&mut context, &mut contract, accounts(1), "1", &locked_msg());
+        let position = contract.get_position("1".to_string()).unwrap();
+        assert_eq!(position.multiplier, 20_000);
+        assert_eq!(position.unlock_at, START + LOCK);
+    }
+
+    #[test]
+    fn test_stake_by_approved_sender() {
+        let (mut context, mut contract) = setup();
+        at(&mut context, land_contract(), START);
+        contract.nft_on_transfer(
+            accounts(2).into(),
+            accounts(1).into(),
+            "1".to_string(),
+            "".to_string(),
+        );
+        let position = contract.get_position("1".to_string()).unwrap();
+        assert_eq!(position.owner_id, accounts(1).to_string());
+        assert!(contract.get_positions(accounts(2), None, None).is_empty());
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                "Staked land 1 of @bob until {} with a multiplier of {}, sent by @charlie",
+                START, MULTIPLIER_BASE
+            )]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only lands can be staked")]
+    fn test_stake_not_land() {
+        let (_, mut contract) = setup();
+        contract.nft_on_transfer(
+            accounts(1).into(),
+            accounts(1).into(),
+            "1".to_string(),
+            "".to_string(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "No lock option of 5")]
+    fn test_stake_unknown_lock() {
+        let (mut context, mut contract) = setup();
+        stake(
+            &mut context,
+            &mut contract,
+            accounts(1),
+            "1",
+            r#"{"lock_duration": 5}"#,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The staking feature is paused")]
+    fn test_stake_paused() {
+        let (mut context, mut contract) = setup();
+        contract.pause(Some(Feature::Staking));
+        stake(&mut context, &mut contract, accounts(1), "1", "");
+    }
+
+    #[test]
+    fn test_rewards_with_multiplier() {
+        let (mut context, mut contract) = setup();
+        stake(&mut context, &mut contract, accounts(1), "1", "");
+        stake(&mut context, &mut contract, accounts(1), "2", &locked_msg());
+        stake(&mut context, &mut contract, accounts(2), "3", "");
+
+        at(&mut context, accounts(1), START + 10 * SECOND);
+        assert_eq!(
+            contract.get_position("2".to_string()).unwrap().rewards.0,
+            20 * RATE
+        );
+        assert_eq!(
+            contract
+                .get_pending_rewards(accounts(1), lands(&["1", "2"]))
+                .0,
+            30 * RATE
+        );
+        assert_eq!(
+            contract
+                .get_claimable_rewards(accounts(1), lands(&["1", "2"]))
+                .0,
+            10 * RATE
+        );
+        assert_eq!(
+            contract.get_pending_rewards(accounts(2), lands(&["3"])).0,
+            10 * RATE
+        );
+    }
+
+    #[test]
+    fn test_set_staking_config_settles_rate() {
+        let (mut context, mut contract) = setup();
+        stake(&mut context, &mut contract, accounts(1), "1", "");
+        at(&mut context, accounts(0), START + 10 * SECOND);
+        let mut config = staking_config();
+        config.reward_rate = U128(2 * RATE);
+        contract.set_staking_config(config);
+
+        at(&mut context, accounts(1), START + 20 * SECOND);
+        assert_eq!(
+            contract.get_pending_rewards(accounts(1), lands(&["1"])).0,
+            30 * RATE
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller is not owner.")]
+    fn test_set_staking_config_not_owner() {
+        let (mut context, mut contract) = setup();
+        at(&mut context, accounts(1), START);
+        contract.set_staking_config(staking_config());
+    }
+
+    #[test]
+    fn test_claim_rewards() {
+        let (mut context, mut contract) = setup();
+        fund(&mut context, &mut contract, 1_000 * RATE);
+        stake(&mut context, &mut contract, accounts(1), "1", "");
+        stake(&mut context, &mut contract, accounts(1), "2", &locked_msg());
+
+        at(&mut context, accounts(1), START + 10 * SECOND);
+        contract.claim_rewards(lands(&["1", "2"]));
+        assert_eq!(contract.get_reward_pool().0, 990 * RATE);
+        assert_eq!(
+            contract
+                .get_claimable_rewards(accounts(1), lands(&["1", "2"]))
+                .0,
+            0
+        );
+        // The locked land keeps its rewards until the lock ends.
+        assert_eq!(
+            contract
+                .get_pending_rewards(accounts(1), lands(&["1", "2"]))
+                .0,
+            20 * RATE
+        );
+
+        at(&mut context, accounts(1), START + LOCK);
+        contract.claim_rewards(lands(&["1", "2"]));
+        assert_eq!(
+            contract
+                .get_pending_rewards(accounts(1), lands(&["1", "2"]))
+                .0,
+            0
+        );
+        assert_eq!(contract.get_reward_pool().0, (1_000 - 100 - 200) * RATE);
+    }
+
+    #[test]
+    #[should_panic(expected = "No rewards to claim")]
+    fn test_claim_rewards_locked() {
+        let (mut context, mut contract) = setup();
+        fund(&mut context, &mut contract, 1_000 * RATE);
+        stake(&mut context, &mut contract, accounts(1), "1", &locked_msg());
+        at(&mut context, accounts(1), START + 10 * SECOND);
+        contract.claim_rewards(lands(&["1"]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the staker can claim the rewards of this land")]
+    fn test_claim_rewards_not_staker() {
+        let (mut context, mut contract) = setup();
+        fund(&mut context, &mut contract, 1_000 * RATE);
+        stake(&mut context, &mut contract, accounts(1), "1", "");
+        at(&mut context, accounts(2), START + 10 * SECOND);
+        contract.claim_rewards(lands(&["1"]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Can claim the rewards of at most 20 lands at once")]
+    fn test_claim_rewards_too_many_lands() {
+        let (mut context, mut contract) = setup();
+        at(&mut context, accounts(1), START);
+        let land_ids = (0..21).map(|i| i.to_string()).collect();
+        contract.claim_rewards(land_ids);
+    }
+
+    #[test]
+    fn test_get_positions_paginated() {
+        let (mut context, mut contract) = setup();
+        for land_id in ["1", "2", "3"] {
+            stake(&mut context, &mut contract, accounts(1), land_id, "");
+        }
+        assert_eq!(contract.get_position_count(accounts(1)).0, 3);
+        let page = contract.get_positions(accounts(1), Some(U64(1)), Some(5));
+        assert_eq!(
+            page.iter()
+                .map(|position| position.land_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["2", "3"]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The reward pool cannot cover 10000 rewards")]
+    fn test_claim_rewards_pool_too_low() {
+        let (mut context, mut contract) = setup();
+        fund(&mut context, &mut contract, RATE);
+        stake(&mut context, &mut contract, accounts(1), "1", "");
+        at(&mut context, accounts(1), START + 10 * SECOND);
+        contract.claim_rewards(lands(&["1"]));
+    }
+
+    #[test]
+    fn test_resolve_claim_failed() {
+        let (mut context, mut contract) = setup();
+        fund(&mut context, &mut contract, 1_000 * RATE);
+        stake(&mut context, &mut contract, accounts(1), "1", "");
+        at(&mut context, accounts(1), START + 10 * SECOND);
+        contract.claim_rewards(lands(&["1"]));
+
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            PromiseResult::Failed,
+        );
+        let paid = contract.resolve_claim(accounts(1).into(), U128(10 * RATE));
+        assert_eq!(paid.0, 0);
+        assert_eq!(
+            contract.get_claimable_rewards(accounts(1), vec![]).0,
+            10 * RATE
+        );
+        assert_eq!(contract.get_reward_pool().0, 1_000 * RATE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the reward token can fund rewards")]
+    fn test_fund_not_reward_token() {
+        let (_, mut contract) = setup();
+        contract.ft_on_transfer(accounts(0), U128(RATE), "".to_string());
+    }
+
+    /// Unstakes the land and resolves the land transfer with `result`.
+    fn unstake(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        owner_id: ValidAccountId,
+        land_id: &str,
+        now: u64,
+        result: PromiseResult,
+    ) -> bool {
+        at(context, owner_id, now);
+        let mut position = contract.get_position(land_id.to_string()).unwrap();
+        let penalty = if position.is_locked() {
+            position.rewards.0 * Balance::from(contract.config.early_unstake_penalty)
+                / Balance::from(MULTIPLIER_BASE)
+        } else {
+            0
+        };
+        contract.unstake(land_id.to_string());
+        // The land is settled when unstaked.
+        position.reward_index = U128(contract.reward_index);
+        testing_env_with_promise_results(
+            context.predecessor_account_id(accounts(0)).build(),
+            result,
+        );
+        contract.resolve_unstake(position, U128(penalty))
+    }
+
+    #[test]
+    #[should_panic(expected = "Reward funding takes no message")]
+    fn test_fund_with_message() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.predecessor_account_id(reward_token()).build());
+        contract.ft_on_transfer(accounts(0), U128(1_000), "stake".to_string());
+    }
+
+    #[test]
+    fn test_unstake_after_lock() {
+        let (mut context, mut contract) = setup();
+        stake(&mut context, &mut contract, accounts(1), "1", &locked_msg());
+        assert!(unstake(
+            &mut context,
+            &mut contract,
+            accounts(1),
+            "1",
+            START + LOCK,
+            PromiseResult::Successful(vec![]),
+        ));
+        assert!(contract.get_position("1".to_string()).is_none());
+        assert!(contract.get_positions(accounts(1), None, None).is_empty());
+        assert_eq!(contract.get_total_staked().0, 0);
+        assert_eq!(
+            contract.get_claimable_rewards(accounts(1), vec![]).0,
+            200 * RATE
+        );
+    }
+
+    #[test]
+    fn test_unstake_early_penalty() {
+        let (mut context, mut contract) = setup();
+        stake(&mut context, &mut contract, accounts(1), "1", &locked_msg());
+        assert!(unstake(
+            &mut context,
+            &mut contract,
+            accounts(1),
+            "1",
+            START + 10 * SECOND,
+            PromiseResult::Successful(vec![]),
+        ));
+        assert_eq!(
+            contract.get_claimable_rewards(accounts(1), vec![]).0,
+            10 * RATE
+        );
+        assert_eq!(contract.get_reward_pool().0, 10 * RATE);
+    }
+
+    #[test]
+    fn test_unstake_transfer_failed() {
+        let (mut context, mut contract) = setup();
+        stake(&mut context, &mut contract, accounts(1), "1", &locked_msg());
+        assert!(!unstake(
+            &mut context,
+            &mut contract,
+            accounts(1),
+            "1",
+            START + 10 * SECOND,
+            PromiseResult::Failed,
+        ));
+        let position = contract.get_position("1".to_string()).unwrap();
+        assert_eq!(position.unlock_at, START + LOCK);
+        assert_eq!(position.rewards.0, 20 * RATE);
+        assert_eq!(contract.get_total_staked().0, 1);
+        assert_eq!(contract.get_reward_pool().0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the staker can unstake this land")]
+    fn test_unstake_not_staker() {
+        let (mut context, mut contract) = setup();
+        stake(&mut context, &mut contract, accounts(1), "1", "");
+        at(&mut context, accounts(2), START);
+        contract.unstake("1".to_string());
+    }
+}
@@ -0,0 +1,197 @@
+use crate::*;
+use near_sdk::{ext_contract, Gas, PromiseResult};
+
+const GAS_FOR_REWARD_TRANSFER: Gas = 15_000_000_000_000;
+const GAS_FOR_RESOLVE_CLAIM: Gas = 10_000_000_000_000;
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+/// Keeps the settling in `claim_rewards` within its gas budget.
+const MAX_LANDS_PER_CLAIM: usize = 20;
+
+#[ext_contract(ext_reward_token)]
+trait RewardToken {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+#[ext_contract(ext_rewards)]
+trait RewardsResolver {
+    fn resolve_claim(&mut self, account_id: AccountId, amount: U128) -> U128;
+}
+
+pub(crate) fn now_in_seconds() -> u64 {
+    env::block_timestamp() / NANOS_PER_SECOND
+}
+
+impl Contract {
+    /// Reward a land staked without a lock earned since the contract was created.
+    fn current_reward_index(&self) -> Balance {
+        self.reward_index
+            + self.config.reward_rate.0
+                * Balance::from(now_in_seconds().saturating_sub(self.reward_index_updated))
+    }
+
+    pub(crate) fn internal_update_reward_index(&mut self) {
+        self.reward_index = self.current_reward_index();
+        self.reward_index_updated = now_in_seconds();
+    }
+
+    fn earned(&self, position: &StakePosition) -> Balance {
+        (self.current_reward_index() - position.reward_index.0) * Balance::from(position.multiplier)
+            / Balance::from(MULTIPLIER_BASE)
+    }
+
+    /// Moves what the position earned so far to its rewards.
+    pub(crate) fn internal_settle(&mut self, position: &mut StakePosition) {
+        self.internal_update_reward_index();
+        position.rewards = U128(position.rewards.0 + self.earned(position));
+        position.reward_index = U128(self.reward_index);
+    }
+
+    pub(crate) fn internal_current_position(&self, mut position: StakePosition) -> StakePosition {
+        position.rewards = U128(position.rewards.0 + self.earned(&position));
+        position
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Funds the reward pool with reward tokens sent with `ft_transfer_call` and an empty
+    /// message. Other messages panic, so the token refunds the transfer.
+    pub fn ft_on_transfer(
+        &mut self,
+        sender_id: ValidAccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.reward_token_id,
+            "Only the reward token can fund rewards"
+        );
+        assert!(msg.is_empty(), "Reward funding takes no message");
+        self.reward_pool += amount.0;
+        env::log(format!("Funded {} rewards from @{}", amount.0, sender_id.as_ref()).as_bytes());
+        PromiseOrValue::Value(U128(0))
+    }
+
+    /// Pays the caller the rewards of their unstaked lands and of `land_ids`, which the caller
+    /// must have staked, up to `MAX_LANDS_PER_CLAIM` lands at once. Rewards of locked lands are
+    /// paid once their lock ends. Leave `land_ids` empty to claim only unstaked lands.
+    pub fn claim_rewards(&mut self, land_ids: Vec<TokenId>) -> Promise {
+        let account_id = env::predecessor_account_id();
+        let positions = self.internal_positions_for(&account_id, &land_ids);
+        let mut total = self.unclaimed_rewards.remove(&account_id).unwrap_or(0);
+        for mut position in positions {
+            if position.is_locked() {
+                continue;
+            }
+            self.internal_settle(&mut position);
+            total += position.rewards.0;
+            position.rewards = U128(0);
+            self.positions.insert(&position.land_id, &position);
+        }
+        assert!(total > 0, "No rewards to claim");
+        assert!(
+            total <= self.reward_pool,
+            "The reward pool cannot cover {} rewards",
+            total
+        );
+        self.reward_pool -= total;
+        env::log(format!("Claimed {} rewards for @{}", total, account_id).as_bytes());
+
+        ext_reward_token::ft_transfer(
+            account_id.clone(),
+            U128(total),
+            Some(String::from("staking rewards")),
+            &self.reward_token_id,
+            1,
+            GAS_FOR_REWARD_TRANSFER,
+        )
+        .then(ext_rewards::resolve_claim(
+            account_id,
+            U128(total),
+            &env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_CLAIM,
+        ))
+    }
+
+    /// Gives the account its rewards back if the payout failed, and returns the amount paid.
+    #[private]
+    pub fn resolve_claim(&mut self, account_id: AccountId, amount: U128) -> U128 {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return amount;
+        }
+        let unclaimed = self.unclaimed_rewards.get(&account_id).unwrap_or(0);
+        self.unclaimed_rewards
+            .insert(&account_id, &(unclaimed + amount.0));
+        self.reward_pool += amount.0;
+        env::log(format!("Reward payout of {} failed and was restored", amount.0).as_bytes());
+        U128(0)
+    }
+
+    /// Rewards of the account's unstaked lands and of `land_ids`, locked or not.
+    pub fn get_pending_rewards(&self, account_id: ValidAccountId, land_ids: Vec<TokenId>) -> U128 {
+        self.internal_rewards_of(account_id.as_ref(), &land_ids, true)
+    }
+
+    /// Rewards `claim_rewards` would pay the account now for `land_ids`.
+    pub fn get_claimable_rewards(
+        &self,
+        account_id: ValidAccountId,
+        land_ids: Vec<TokenId>,
+    ) -> U128 {
+        self.internal_rewards_of(account_id.as_ref(), &land_ids, false)
+    }
+
+    pub fn get_reward_pool(&self) -> U128 {
+        U128(self.reward_pool)
+    }
+}
+
+impl Contract {
+    /// Positions of `land_ids`, which must all be staked by the account.
+    fn internal_positions_for(
+        &self,
+        account_id: &AccountId,
+        land_ids: &[TokenId],
+    ) -> Vec<StakePosition> {
+        assert!(
+            land_ids.len() <= MAX_LANDS_PER_CLAIM,
+            "Can claim the rewards of at most {} lands at once",
+            MAX_LANDS_PER_CLAIM
+        );
+        land_ids
+            .iter()
+            .enumerate()
+            .map(|(i, land_id)| {
+                assert!(
+                    !land_ids[..i].contains(land_id),
+                    "Cannot claim land {} twice",
+                    land_id
+                );
+                let position = self.positions.get(land_id).expect("Land is not staked");
+                assert_eq!(
+                    &position.owner_id, account_id,
+                    "Only the staker can claim the rewards of this land"
+                );
+                position
+            })
+            .collect()
+    }
+
+    fn internal_rewards_of(
+        &self,
+        account_id: &AccountId,
+        land_ids: &[TokenId],
+        include_locked: bool,
+    ) -> U128 {
+        let unclaimed = self.unclaimed_rewards.get(account_id).unwrap_or(0);
+        let staked: Balance = self
+            .internal_positions_for(account_id, land_ids)
+            .into_iter()
+            .filter(|position| include_locked || !position.is_locked())
+            .map(|position| position.rewards.0 + self.earned(&position))
+            .sum();
+        U128(unclaimed + staked)
+    }
+}
@@ -0,0 +1,224 @@
+use crate::*;
+use near_sdk::serde_json;
+use near_sdk::{ext_contract, Gas, PromiseResult};
+
+const GAS_FOR_LAND_TRANSFER: Gas = 15_000_000_000_000;
+const GAS_FOR_RESOLVE_UNSTAKE: Gas = 15_000_000_000_000;
+const MAX_POSITIONS_PER_PAGE: u64 = 50;
+
+/// Message of the land contract's `nft_transfer_call` staking a land. An empty message stakes
+/// the land without a lock.
+#[derive(Deserialize, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakeMessage {
+    /// One of the configured lock durations, in nanoseconds. No lock if 0.
+    #[serde(default)]
+    pub lock_duration: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakePosition {
+    pub land_id: TokenId,
+    pub owner_id: AccountId,
+    /// Applied to the reward rate, in basis points of [`MULTIPLIER_BASE`].
+    pub multiplier: u32,
+    pub staked_at: Timestamp,
+    /// The land can be unstaked without penalty from this time on.
+    pub unlock_at: Timestamp,
+    /// Rewards earned and not claimed yet.
+    pub rewards: U128,
+    /// Reward index the position was last settled at.
+    pub reward_index: U128,
+}
+
+impl StakePosition {
+    pub fn is_locked(&self) -> bool {
+        env::block_timestamp() < self.unlock_at
+    }
+}
+
+#[ext_contract(ext_land)]
+trait LandContract {
+    fn nft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<U64>,
+        memo: Option<String>,
+    );
+}
+
+#[ext_contract(ext_staking)]
+trait StakingResolver {
+    fn resolve_unstake(&mut self, position: StakePosition, penalty: U128) -> bool;
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Stakes a land sent with `nft_transfer_call` on the land contract for its previous owner.
+    /// The message picks a lock, e.g. `{"lock_duration": 2592000000000000}`. Invalid messages
+    /// panic, so the land contract returns the land.
+    pub fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.land_contract_id,
+            "Only lands can be staked"
+        );
+        self.pausable.assert_not_paused(Feature::Staking);
+        let StakeMessage { lock_duration } = if msg.is_empty() {
+            StakeMessage::default()
+        } else {
+            serde_json::from_str(&msg).expect("Invalid stake message")
+        };
+        let multiplier = self.config.multiplier_for(lock_duration);
+
+        self.internal_update_reward_index();
+        let staked_at = env::block_timestamp();
+        // Approved senders stake lands for the owner, who earns the rewards.
+        let position = StakePosition {
+            land_id: token_id.clone(),
+            owner_id: previous_owner_id.clone(),
+            multiplier,
+            staked_at,
+            unlock_at: staked_at + lock_duration,
+            rewards: U128(0),
+            reward_index: U128(self.reward_index),
+        };
+        self.positions.insert(&token_id, &position);
+        self.internal_add_position_to_owner(&previous_owner_id, &token_id);
+        self.total_staked += 1;
+        env::log(
+            format!(
+                "Staked land {} of @{} until {} with a multiplier of {}, sent by @{}",
+                token_id, previous_owner_id, position.unlock_at, multiplier, sender_id
+            )
+            .as_bytes(),
+        );
+        PromiseOrValue::Value(false)
+    }
+
+    /// Returns the caller's land. Unstaking before the lock ends forfeits the early unstake
+    /// penalty of the land's unclaimed rewards. The rest can be claimed with `claim_rewards`.
+    #[payable]
+    pub fn unstake(&mut self, land_id: TokenId) -> Promise {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        let mut position = self.positions.get(&land_id).expect("Land is not staked");
+        assert_eq!(
+            position.owner_id, owner_id,
+            "Only the staker can unstake this land"
+        );
+        self.internal_settle(&mut position);
+        self.positions.remove(&land_id);
+        self.internal_remove_position_from_owner(&owner_id, &land_id);
+        self.total_staked -= 1;
+
+        let penalty = if position.is_locked() {
+            position.rewards.0 * Balance::from(self.config.early_unstake_penalty)
+                / Balance::from(MULTIPLIER_BASE)
+        } else {
+            0
+        };
+        env::log(
+            format!(
+                "Unstaked land {} of @{} with a penalty of {}",
+                land_id, owner_id, penalty
+            )
+            .as_bytes(),
+        );
+
+        ext_land::nft_transfer(
+            owner_id,
+            land_id,
+            None,
+            Some(String::from("unstake")),
+            &self.land_contract_id,
+            1,
+            GAS_FOR_LAND_TRANSFER,
+        )
+        .then(ext_staking::resolve_unstake(
+            position,
+            U128(penalty),
+            &env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_UNSTAKE,
+        ))
+    }
+
+    /// Credits the rewards of an unstaked land to its owner, or stakes the land again if it
+    /// could not be returned.
+    #[private]
+    pub fn resolve_unstake(&mut self, position: StakePosition, penalty: U128) -> bool {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            let unclaimed = self.unclaimed_rewards.get(&position.owner_id).unwrap_or(0);
+            self.unclaimed_rewards.insert(
+                &position.owner_id,
+                &(unclaimed + position.rewards.0 - penalty.0),
+            );
+            self.reward_pool += penalty.0;
+            return true;
+        }
+        self.positions.insert(&position.land_id, &position);
+        self.internal_add_position_to_owner(&position.owner_id, &position.land_id);
+        self.total_staked += 1;
+        env::log(
+            format!(
+                "Land {} could not be returned and is staked again",
+                position.land_id
+            )
+            .as_bytes(),
+        );
+        false
+    }
+
+    /// The land's position with its rewards up to now.
+    pub fn get_position(&self, land_id: TokenId) -> Option<StakePosition> {
+        self.positions
+            .get(&land_id)
+            .map(|position| self.internal_current_position(position))
+    }
+
+    /// Positions of the account starting at `from_index`, at most `MAX_POSITIONS_PER_PAGE` of
+    /// them.
+    pub fn get_positions(
+        &self,
+        account_id: ValidAccountId,
+        from_index: Option<U64>,
+        limit: Option<u64>,
+    ) -> Vec<StakePosition> {
+        let land_ids = match self.positions_per_owner.get(account_id.as_ref()) {
+            Some(land_ids) => land_ids,
+            None => return Vec::new(),
+        };
+        let limit = limit
+            .unwrap_or(MAX_POSITIONS_PER_PAGE)
+            .min(MAX_POSITIONS_PER_PAGE);
+        land_ids
+            .as_vector()
+            .iter()
+            .skip(from_index.map_or(0, |index| index.0) as usize)
+            .take(limit as usize)
+            .filter_map(|land_id| self.positions.get(&land_id))
+            .map(|position| self.internal_current_position(position))
+            .collect()
+    }
+
+    /// How many lands the account has staked.
+    pub fn get_position_count(&self, account_id: ValidAccountId) -> U64 {
+        U64(self
+            .positions_per_owner
+            .get(account_id.as_ref())
+            .map_or(0, |land_ids| land_ids.len()))
+    }
+
+    pub fn get_total_staked(&self) -> U64 {
+        U64(self.total_staked)
+    }
+}
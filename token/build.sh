@@ -0,0 +1,5 @@
+#!/bin/bash
+set -e
+cd "`dirname $0`"
+cargo build -p scity-token --target wasm32-unknown-unknown --release
+cp ../target/wasm32-unknown-unknown/release/scity_token.wasm ./res/scity.wasm
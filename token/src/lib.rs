@@ -0,0 +1,318 @@
+use near_contract_standards::fungible_token::metadata::{
+    FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC,
+};
+use near_contract_standards::fungible_token::FungibleToken;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LazyOption;
+use near_sdk::json_types::{ValidAccountId, U128};
+use near_sdk::{env, log, near_bindgen, AccountId, Balance, PanicOnDefault};
+
+pub use scity_access::*;
+pub use scity_ft_core::*;
+
+near_sdk::setup_alloc!();
+
+/// SCITY, the game token mining rewards are paid in and upgrades are bought with.
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    token: FungibleToken,
+    metadata: LazyOption<FungibleTokenMetadata>,
+    /// Tokens ever minted can never exceed this amount, burned tokens are not minted again.
+    max_supply: Balance,
+    /// Tokens minted so far, including those burned since.
+    total_minted: Balance,
+    /// The reward contract, the only account allowed to mint.
+    minter_id: AccountId,
+    owner_id: AccountId,
+    pending_owner_id: Option<AccountId>,
+    access: AccessControl,
+    pausable: Pausable,
+}
+
+const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
+
+#[near_bindgen]
+impl Contract {
+    /// Initializes the contract with no supply and default metadata. Tokens are only minted by
+    /// `minter_id`, up to `max_supply`.
+    #[init]
+    pub fn new_default_meta(
+        owner_id: ValidAccountId,
+        minter_id: ValidAccountId,
+        max_supply: U128,
+    ) -> Self {
+        Self::new(
+            owner_id,
+            minter_id,
+            max_supply,
+            FungibleTokenMetadata {
+                spec: FT_METADATA_SPEC.to_string(),
+                name: "Scity".to_string(),
+                symbol: "SCITY".to_string(),
+                icon: Some(DATA_IMAGE_SVG_NEAR_ICON.to_string()),
+                reference: None,
+                reference_hash: None,
+                decimals: 18,
+            },
+        )
+    }
+
+    /// Initializes the contract with no supply and the given fungible token metadata.
+    #[init]
+    pub fn new(
+        owner_id: ValidAccountId,
+        minter_id: ValidAccountId,
+        max_supply: U128,
+        metadata: FungibleTokenMetadata,
+    ) -> Self {
+        assert!(!env::state_exists(), "Already initialized");
+        metadata.assert_valid();
+        assert!(max_supply.0 > 0, "Max supply must be positive");
+        Self {
+            token: FungibleToken::new(b"a".to_vec()),
+            metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
+            max_supply: max_supply.0,
+            total_minted: 0,
+            minter_id: minter_id.into(),
+            owner_id: owner_id.into(),
+            pending_owner_id: None,
+            access: AccessControl::new(b"r".to_vec()),
+            pausable: Pausable::default(),
+        }
+    }
+
+    fn assert_owner(&self) {
+        assert!(
+            env::predecessor_account_id() == self.owner_id,
+            "Caller is not owner."
+        );
+    }
+
+    fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
+        log!("Closed @{} with {}", account_id, balance);
+    }
+
+    fn on_tokens_burned(&mut self, account_id: AccountId, amount: Balance) {
+        log!("Account @{} burned {}", account_id, amount);
+    }
+
+    /// Mints rewards to a registered account. Only the reward contract can mint.
+    #[payable]
+    pub fn ft_mint(&mut self, account_id: ValidAccountId, amount: U128, memo: Option<String>) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.minter_id,
+            "Only the reward contract can mint"
+        );
+        self.pausable.assert_not_paused(Feature::Minting);
+        let amount: Balance = amount.into();
+        assert!(amount > 0, "The amount should be a positive number");
+        assert!(
+            self.total_minted + amount <= self.max_supply,
+            "Minting would exceed the max supply of {}",
+            self.max_supply
+        );
+        self.token.internal_deposit(account_id.as_ref(), amount);
+        self.total_minted += amount;
+        emit_ft_mint(account_id.as_ref(), amount, memo.as_deref());
+    }
+
+    /// Burns tokens held by the caller, e.g. the company contract after an upgrade is paid.
+    #[payable]
+    pub fn ft_burn(&mut self, amount: U128, memo: Option<String>) {
+        assert_eq!(
+            env::attached_deposit(),
+            1,
+            "Requires attached deposit of exactly 1 yoctoNEAR",
+        );
+        let account_id = env::predecessor_account_id();
+        let amount: Balance = amount.into();
+        assert!(amount > 0, "The amount should be a positive number");
+        self.token.internal_withdraw(&account_id, amount);
+        emit_ft_burn(&account_id, amount, memo.as_deref());
+        self.on_tokens_burned(account_id, amount);
+    }
+
+    pub fn set_minter(&mut self, minter_id: ValidAccountId) {
+        self.assert_owner();
+        emit_config_updated(
+            "minter_id",
+            &self.minter_id,
+            minter_id.as_ref(),
+            &env::predecessor_account_id(),
+        );
+        self.minter_id = minter_id.into();
+    }
+
+    pub fn get_minter(&self) -> AccountId {
+        self.minter_id.clone()
+    }
+
+    pub fn get_max_supply(&self) -> U128 {
+        U128(self.max_supply)
+    }
+
+    /// Tokens minted so far, including those burned since.
+    pub fn get_total_minted(&self) -> U128 {
+        U128(self.total_minted)
+    }
+}
+
+near_contract_standards::impl_fungible_token_storage!(Contract, token, on_account_closed);
+scity_ft_core::impl_fungible_token_core!(Contract, token, pausable, on_tokens_burned);
+scity_access::impl_access_control!(Contract, access, owner_id);
+scity_access::impl_ownership!(Contract, owner_id, pending_owner_id);
+scity_access::impl_pausable!(Contract, pausable);
+
+#[near_bindgen]
+impl FungibleTokenMetadataProvider for Contract {
+    fn ft_metadata(&self) -> FungibleTokenMetadata {
+        self.metadata.get().unwrap()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::core::FungibleTokenCore;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk::MockedBlockchain;
+
+    use super::*;
+
+    const MAX_SUPPLY: Balance = 1_000_000;
+
+    fn get_context(predecessor_account_id: ValidAccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(5))
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id);
+        builder
+    }
+
+    /// Creates the token with `accounts(0)` as owner and `accounts(3)` as the reward
+    /// contract, and registers `accounts(1)`.
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0), accounts(3), U128(MAX_SUPPLY));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .build());
+        contract.storage_deposit(None, None);
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(1)
+            .build());
+        (context, contract)
+    }
+
+    #[test]
+    fn test_new() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(0), accounts(3), U128(MAX_SUPPLY));
+        assert_eq!(contract.ft_total_supply().0, 0);
+        assert_eq!(contract.get_max_supply().0, MAX_SUPPLY);
+        assert_eq!(contract.get_minter(), accounts(3).to_string());
+        assert_eq!(contract.ft_metadata().symbol, "SCITY");
+    }
+
+    #[test]
+    fn test_mint() {
+        let (_, mut contract) = setup();
+        contract.ft_mint(accounts(1), U128(1_000), Some("mining rewards".to_string()));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1_000);
+        assert_eq!(contract.ft_total_supply().0, 1_000);
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[{"owner_id":"bob","amount":"1000","memo":"mining rewards"}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the reward contract can mint")]
+    fn test_mint_not_minter() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.ft_mint(accounts(1), U128(1_000), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Minting would exceed the max supply of 1000000")]
+    fn test_mint_above_max_supply() {
+        let (_, mut contract) = setup();
+        contract.ft_mint(accounts(1), U128(MAX_SUPPLY), None);
+        contract.ft_mint(accounts(1), U128(1), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account charlie is not registered")]
+    fn test_mint_unregistered() {
+        let (_, mut contract) = setup();
+        contract.ft_mint(accounts(2), U128(1_000), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The minting feature is paused")]
+    fn test_mint_paused() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.pause(Some(Feature::Minting));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.ft_mint(accounts(1), U128(1_000), None);
+    }
+
+    #[test]
+    fn test_burn() {
+        let (mut context, mut contract) = setup();
+        contract.ft_mint(accounts(1), U128(1_000), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_burn(U128(400), Some("upgrade_company".to_string()));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 600);
+        assert_eq!(contract.ft_total_supply().0, 600);
+        assert_eq!(contract.get_total_minted().0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Minting would exceed the max supply of 1000000")]
+    fn test_mint_after_burn_above_max_supply() {
+        let (mut context, mut contract) = setup();
+        contract.ft_mint(accounts(1), U128(MAX_SUPPLY), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_burn(U128(400), None);
+
+        // Burned tokens are not minted again.
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.ft_mint(accounts(1), U128(1), None);
+    }
+
+    #[test]
+    fn test_set_minter() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_minter(accounts(4));
+        assert_eq!(contract.get_minter(), accounts(4).to_string());
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"scity_access","version":"1.0.0","event":"config_updated","data":[{"field":"minter_id","old":"danny","new":"eugene","sender_id":"alice"}]}"#
+            ]
+        );
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        contract.ft_mint(accounts(1), U128(1), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller is not owner.")]
+    fn test_set_minter_not_owner() {
+        let (mut context, mut contract) = setup();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.set_minter(accounts(1));
+    }
+}